@@ -0,0 +1,89 @@
+//! The attributes of the `taint-annotations` crate, which is where they should be used from.
+//!
+//! Each attribute leaves the item it is on as it is,
+//! and adds the same attribute in the `taint_marker` namespace for the analysis to find,
+//! behind the `taint_analysis` cfg which only the `taint` driver sets,
+//! e.g. `#[taint::sink(label = "log")]` adds `#[cfg_attr(taint_analysis, taint_marker::sink(label = "log"))]`.
+//! The driver registers the `taint_marker` tool, which no other compiler knows of.
+//! The arguments are checked by the analysis rather than here.
+
+extern crate proc_macro;
+
+use proc_macro::{Delimiter, Group, Ident, Punct, Spacing, Span, TokenStream, TokenTree};
+
+/// Marks a function whose return value is tainted.
+#[proc_macro_attribute]
+pub fn source(args: TokenStream, item: TokenStream) -> TokenStream {
+    marker("source", args, item)
+}
+
+/// Marks a function which must not receive tainted arguments, optionally with a label.
+#[proc_macro_attribute]
+pub fn sink(args: TokenStream, item: TokenStream) -> TokenStream {
+    marker("sink", args, item)
+}
+
+/// Marks a function whose return value is not tainted, whatever its arguments.
+#[proc_macro_attribute]
+pub fn sanitizer(args: TokenStream, item: TokenStream) -> TokenStream {
+    marker("sanitizer", args, item)
+}
+
+/// Opts a function out of the role of the impl block or module it is in.
+#[proc_macro_attribute]
+pub fn none(args: TokenStream, item: TokenStream) -> TokenStream {
+    marker("none", args, item)
+}
+
+/// Marks a struct, enum or union whose values are always tainted.
+#[proc_macro_attribute]
+pub fn tainted_type(args: TokenStream, item: TokenStream) -> TokenStream {
+    marker("tainted_type", args, item)
+}
+
+/// Marks a type which makes every function taking it a sink.
+#[proc_macro_attribute]
+pub fn sink_type(args: TokenStream, item: TokenStream) -> TokenStream {
+    marker("sink_type", args, item)
+}
+
+/// Suppresses the findings in an item, with a reason.
+#[proc_macro_attribute]
+pub fn allow(args: TokenStream, item: TokenStream) -> TokenStream {
+    marker("allow", args, item)
+}
+
+/// `#[cfg_attr(taint_analysis, taint_marker::<name>(<args>))]` followed by the item.
+fn marker(name: &str, args: TokenStream, item: TokenStream) -> TokenStream {
+    let span = Span::call_site();
+    let mut marker = vec![
+        TokenTree::Ident(Ident::new("taint_analysis", span)),
+        TokenTree::Punct(Punct::new(',', Spacing::Alone)),
+        TokenTree::Ident(Ident::new("taint_marker", span)),
+        TokenTree::Punct(Punct::new(':', Spacing::Joint)),
+        TokenTree::Punct(Punct::new(':', Spacing::Alone)),
+        TokenTree::Ident(Ident::new(name, span)),
+    ];
+    if !args.is_empty() {
+        marker.push(TokenTree::Group(Group::new(Delimiter::Parenthesis, args)));
+    }
+    let attribute = vec![
+        TokenTree::Ident(Ident::new("cfg_attr", span)),
+        TokenTree::Group(Group::new(
+            Delimiter::Parenthesis,
+            marker.into_iter().collect(),
+        )),
+    ];
+
+    let mut tokens: TokenStream = vec![
+        TokenTree::Punct(Punct::new('#', Spacing::Alone)),
+        TokenTree::Group(Group::new(
+            Delimiter::Bracket,
+            attribute.into_iter().collect(),
+        )),
+    ]
+    .into_iter()
+    .collect();
+    tokens.extend(item);
+    tokens
+}
@@ -0,0 +1,107 @@
+//! Annotations for the taint analysis, which do nothing when the crate is compiled as usual.
+//!
+//! The crate is meant to be imported under the name `taint`, so that the annotations read e.g. `taint::declassify!(...)`:
+//!
+//! ```toml
+//! [dependencies]
+//! taint = { package = "taint-annotations", version = "0.1" }
+//! ```
+//!
+//! Its attributes, such as `#[taint::source]` and `#[taint::sink(label = "...")]`, work on stable Rust,
+//! unlike the tool attributes of `#![register_tool(taint)]`, which need nightly.
+//! They are proc macros, which can only be put on items,
+//! so `#[taint::allow(reason = "...")]` cannot be put on a statement or expression, and roles cannot be given to a whole crate.
+//! They can be left out with `default-features = false`, e.g. by a crate which only declassifies values.
+//!
+//! ```
+//! # use taint_annotations as taint;
+//! #[taint::source]
+//! fn read_password() -> String {
+//!     String::from("hunter2")
+//! }
+//!
+//! #[taint::sink(label = "log")]
+//! fn log(_: &str) {}
+//!
+//! log(&taint::declassify!(read_password().len().to_string(), "the length is not secret"));
+//! ```
+//!
+//! A crate named `taint` shadows the tool registered with `#![register_tool(taint)]`,
+//! so a crate which still uses the tool attributes has to import it under another name,
+//! e.g. as `taint_annotations`.
+
+#[cfg(feature = "attributes")]
+pub use taint_annotations_macros::{allow, none, sanitizer, sink, sink_type, source, tainted_type};
+
+/// Removes the taint of a value, e.g. once it has been hashed or checked in a way the analysis cannot see.
+///
+/// It returns the value unchanged, and the analysis treats it as a sanitizer for that one place.
+/// The reason must be a string literal, and is listed with every other declassification
+/// by `--taint-report-declassifications=<file>`, so that they can be audited.
+///
+/// ```
+/// # use taint_annotations as taint;
+/// let password = String::from("hunter2");
+/// let length = taint::declassify!(password.len(), "the length of a password is not secret");
+/// assert_eq!(length, 7);
+/// ```
+#[macro_export]
+macro_rules! declassify {
+    ($value:expr, $reason:literal $(,)?) => {
+        $crate::declassify($value, $reason)
+    };
+}
+
+/// Checks that a value may be tainted at this point, which the analysis reports as an error if it is not.
+///
+/// It does nothing when the crate is compiled as usual, and is meant for testing the analysis and its annotations.
+/// The value is only borrowed.
+/// A function may be analyzed several times, e.g. with different arguments tainted,
+/// and the assertion holds if the value is tainted in at least one of them.
+/// An assertion the analysis never reaches does not hold.
+///
+/// ```
+/// # use taint_annotations as taint;
+/// let password = String::from("hunter2");
+/// taint::assert_tainted!(password);
+/// ```
+#[macro_export]
+macro_rules! assert_tainted {
+    ($value:expr $(,)?) => {
+        $crate::assert_tainted(&$value, stringify!($value))
+    };
+}
+
+/// Checks that a value is not tainted at this point, which the analysis reports as an error if it may be.
+///
+/// It does nothing when the crate is compiled as usual, and is meant for testing the analysis and its annotations.
+/// The value is only borrowed, and the assertion must hold in every context the function is analyzed in.
+///
+/// ```
+/// # use taint_annotations as taint;
+/// let length = 7;
+/// taint::assert_clean!(length);
+/// ```
+#[macro_export]
+macro_rules! assert_clean {
+    ($value:expr $(,)?) => {
+        $crate::assert_clean(&$value, stringify!($value))
+    };
+}
+
+/// The function `declassify!` expands to, which the analysis recognizes by its path.
+#[doc(hidden)]
+#[inline(never)]
+pub fn declassify<T>(value: T, _reason: &'static str) -> T {
+    value
+}
+
+/// The function `assert_tainted!` expands to, which the analysis recognizes by its path.
+#[doc(hidden)]
+#[inline(never)]
+pub fn assert_tainted<T: ?Sized>(_value: &T, _expression: &'static str) {}
+
+/// The function `assert_clean!` expands to, which the analysis recognizes by its path.
+#[doc(hidden)]
+#[inline(never)]
+pub fn assert_clean<T: ?Sized>(_value: &T, _expression: &'static str) {}
@@ -0,0 +1,149 @@
+//! Compares the precision and running time of each context sensitivity policy on the programs in `tests/`.
+//!
+//! Run with `cargo bench --bench context_sensitivity`.
+//! For each policy, it reports how long the programs took to analyze,
+//! the errors reported where none is expected (false positives),
+//! and the expected errors which were not reported (false negatives).
+//! Programs which need auxiliary crates, or which were written for a particular policy, are skipped.
+
+use std::{
+    collections::BTreeSet,
+    env, fs,
+    path::{Path, PathBuf},
+    process::Command,
+    time::{Duration, Instant},
+};
+
+const POLICIES: &[&str] = &[
+    "insensitive",
+    "object",
+    "call-string=1",
+    "call-string=2",
+    "arguments",
+];
+
+/// Each program is analyzed this many times, and the fastest run is kept.
+const RUNS: usize = 3;
+
+struct Program {
+    path: PathBuf,
+    flags: Vec<String>,
+    /// The lines where an error is expected.
+    expected: BTreeSet<usize>,
+}
+
+fn main() {
+    let programs = ["tests/passes", "tests/fails"]
+        .iter()
+        .flat_map(|dir| programs(Path::new(dir)))
+        .collect::<Vec<_>>();
+    let out_dir = env::temp_dir().join(format!("taint-bench-{}", std::process::id()));
+    fs::create_dir_all(&out_dir).unwrap();
+
+    println!(
+        "{} programs, fastest of {} runs each\n",
+        programs.len(),
+        RUNS
+    );
+    println!(
+        "{:<16}{:>12}{:>12}{:>18}{:>18}",
+        "policy", "time", "errors", "false positives", "false negatives"
+    );
+
+    for policy in POLICIES {
+        let mut time = Duration::ZERO;
+        let (mut errors, mut false_positives, mut false_negatives) = (0, 0, 0);
+
+        for program in &programs {
+            let (elapsed, found) = (0..RUNS)
+                .map(|_| analyze(program, policy, &out_dir))
+                .min_by_key(|(elapsed, _)| *elapsed)
+                .unwrap();
+            time += elapsed;
+            errors += found.len();
+            false_positives += found.difference(&program.expected).count();
+            false_negatives += program.expected.difference(&found).count();
+        }
+
+        println!(
+            "{:<16}{:>10.2?}{:>12}{:>18}{:>18}",
+            policy, time, errors, false_positives, false_negatives
+        );
+    }
+
+    let _ = fs::remove_dir_all(&out_dir);
+}
+
+fn programs(dir: &Path) -> Vec<Program> {
+    let mut paths = Vec::new();
+    for entry in fs::read_dir(dir).unwrap() {
+        let path = entry.unwrap().path();
+        if path.is_dir() {
+            if !path.ends_with("auxiliary") {
+                paths.extend(programs(&path).into_iter().map(|p| p.path));
+            }
+        } else if path.extension().map_or(false, |ext| ext == "rs") {
+            paths.push(path);
+        }
+    }
+    paths.sort();
+
+    paths
+        .into_iter()
+        .filter_map(|path| {
+            let source = fs::read_to_string(&path).unwrap();
+            if source.contains("// aux-build:") || source.contains("--taint-context=") {
+                return None;
+            }
+
+            let flags = source
+                .lines()
+                .filter_map(|line| line.strip_prefix("// compile-flags:"))
+                .flat_map(|flags| flags.split_whitespace().map(str::to_owned))
+                .collect();
+            let expected = source
+                .lines()
+                .enumerate()
+                .filter(|(_, line)| line.contains("//~ ERROR"))
+                .map(|(i, _)| i + 1)
+                .collect();
+
+            Some(Program {
+                path,
+                flags,
+                expected,
+            })
+        })
+        .collect()
+}
+
+/// Analyze a program and return how long it took, and the lines where an error was reported.
+fn analyze(program: &Program, policy: &str, out_dir: &Path) -> (Duration, BTreeSet<usize>) {
+    let start = Instant::now();
+    let output = Command::new(env!("CARGO_BIN_EXE_taint"))
+        .arg(&program.path)
+        .args(["--edition", "2018"])
+        .arg(format!("--taint-context={}", policy))
+        .args(&program.flags)
+        .arg("--out-dir")
+        .arg(out_dir)
+        .output()
+        .expect("failed to run taint");
+    let elapsed = start.elapsed();
+
+    // Each error is followed by its location, e.g. ` --> tests/fails/simple.rs:12:5`.
+    let stderr = String::from_utf8(output.stderr).unwrap();
+    let mut found = BTreeSet::new();
+    let mut lines = stderr.lines();
+    while let Some(line) = lines.next() {
+        if !line.starts_with("error[") {
+            continue;
+        }
+        let location = lines.find_map(|line| line.trim_start().strip_prefix("--> "));
+        if let Some(line) = location.and_then(|l| l.rsplit(':').nth(1)?.parse().ok()) {
+            found.insert(line);
+        }
+    }
+
+    (elapsed, found)
+}
@@ -0,0 +1,60 @@
+//! Checks the assertions made with `taint::assert_tainted!` and `taint::assert_clean!`, which are used to test the analysis.
+//!
+//! The analysis records whether the value of each assertion it reaches is tainted, in any context.
+//! Once it is over, every assertion in the crate is checked, and those which do not hold are errors.
+//! An `assert_tainted!` the analysis never reaches does not hold, as the value was never seen tainted.
+
+use rustc_data_structures::{fx::FxHashSet, sync::Lock};
+use rustc_errors::error_code;
+use rustc_middle::ty::TyCtxt;
+use rustc_span::Span;
+
+use super::models::{annotation_calls, AnnotationCall, Model};
+
+#[derive(Default)]
+pub struct Assertions {
+    /// The assertions in the crate, whether or not the analysis reaches them.
+    calls: Vec<AnnotationCall>,
+    /// The assertions which were given a tainted value, by the place of the call.
+    tainted: Lock<FxHashSet<Span>>,
+}
+
+impl Assertions {
+    pub fn new(tcx: TyCtxt<'_>) -> Self {
+        let calls = annotation_calls(tcx)
+            .into_iter()
+            .filter(|call| matches!(call.model, Model::AssertTainted | Model::AssertClean))
+            .collect();
+        Assertions {
+            calls,
+            tainted: Lock::new(FxHashSet::default()),
+        }
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.calls.is_empty()
+    }
+
+    /// Record the taint of the value of an assertion in one context.
+    pub(crate) fn record(&self, span: Span, tainted: bool) {
+        if tainted {
+            self.tainted.borrow_mut().insert(span.source_callsite());
+        }
+    }
+
+    /// Report every assertion which does not hold.
+    pub fn emit_errors(&self, tcx: TyCtxt<'_>) {
+        let tainted = self.tainted.borrow();
+        for call in &self.calls {
+            let message = match (call.model, tainted.contains(&call.span)) {
+                (Model::AssertTainted, false) => format!("`{}` is never tainted here", call.text),
+                (Model::AssertClean, true) => format!("`{}` may be tainted here", call.text),
+                _ => continue,
+            };
+            tcx.sess
+                .struct_span_err(call.span, format!("taint assertion failed: {}", message))
+                .code(error_code!(T0007))
+                .emit();
+        }
+    }
+}
@@ -0,0 +1,170 @@
+//! Limits on how much work the analysis does, so that a pathological crate cannot keep it running indefinitely.
+//!
+//! Once a limit is reached, the function being analyzed gets a conservative summary instead,
+//! and a warning names it, since the sinks reached in it may not be reported.
+//! Some limits are on the analysis of each function, and others on the whole analysis,
+//! in which case every function left is cut short once it is reached.
+
+use std::{
+    collections::BTreeMap,
+    time::{Duration, Instant},
+};
+
+use rustc_data_structures::sync::Lock;
+use rustc_hir::def_id::DefId;
+use rustc_middle::ty::{Instance, TyCtxt};
+
+use crate::eval::config::TaintConfig;
+
+const CONSERVATIVE: &str = "everything it returns or writes to is assumed to be tainted, and the sinks it reaches may not be reported";
+
+/// The limit which cut the analysis of a function short.
+#[derive(Clone, Copy, Debug)]
+pub(crate) enum Exceeded {
+    /// It visited more than this many basic blocks.
+    Blocks(usize),
+    /// It was called through more than this many nested analyses.
+    Depth(usize),
+    /// The whole analysis visited more than this many basic blocks.
+    TotalBlocks(usize),
+    /// The whole analysis took more than this many seconds.
+    Timeout(u64),
+}
+
+impl Exceeded {
+    /// Whether the limit is on the whole analysis rather than on the function.
+    fn is_global(self) -> bool {
+        matches!(self, Exceeded::TotalBlocks(_) | Exceeded::Timeout(_))
+    }
+}
+
+#[derive(Debug)]
+pub struct Budget {
+    max_blocks: Option<usize>,
+    max_depth: Option<usize>,
+    max_total_blocks: Option<usize>,
+    timeout: Option<u64>,
+    deadline: Option<Instant>,
+    /// How many basic blocks the whole analysis has visited.
+    visited: Lock<usize>,
+    /// The functions whose analysis was cut short, and the first limit each of them reached.
+    exceeded: Lock<BTreeMap<DefId, Exceeded>>,
+    /// The function which was being analyzed when a limit on the whole analysis was first reached.
+    exhausted: Lock<Option<(DefId, Exceeded)>>,
+}
+
+impl Budget {
+    /// The timeout starts counting once the budget is created.
+    pub fn new(config: &TaintConfig) -> Self {
+        Budget {
+            max_blocks: config.max_blocks,
+            max_depth: config.max_depth,
+            max_total_blocks: config.max_total_blocks,
+            timeout: config.timeout,
+            deadline: config
+                .timeout
+                .map(|timeout| Instant::now() + Duration::from_secs(timeout)),
+            visited: Lock::new(0),
+            exceeded: Lock::new(BTreeMap::new()),
+            exhausted: Lock::new(None),
+        }
+    }
+
+    /// Whether a function may be analyzed, given how many analyses it is nested in.
+    pub(crate) fn check_depth(&self, depth: usize) -> Option<Exceeded> {
+        match self.max_depth {
+            Some(max) if depth >= max => Some(Exceeded::Depth(max)),
+            _ => self.check_global(),
+        }
+    }
+
+    /// Whether the analysis of a function may continue, given how many basic blocks it has visited.
+    /// The block it is about to visit counts towards the blocks of the whole analysis as well.
+    pub(crate) fn check_blocks(&self, visited: usize) -> Option<Exceeded> {
+        *self.visited.borrow_mut() += 1;
+        match self.max_blocks {
+            Some(max) if visited > max => Some(Exceeded::Blocks(max)),
+            _ => self.check_global(),
+        }
+    }
+
+    fn check_global(&self) -> Option<Exceeded> {
+        match self.max_total_blocks {
+            Some(max) if *self.visited.borrow() > max => return Some(Exceeded::TotalBlocks(max)),
+            _ => {}
+        }
+        match (self.deadline, self.timeout) {
+            (Some(deadline), Some(timeout)) if Instant::now() >= deadline => {
+                Some(Exceeded::Timeout(timeout))
+            }
+            _ => None,
+        }
+    }
+
+    pub(crate) fn record(&self, instance: Instance<'_>, exceeded: Exceeded) {
+        if exceeded.is_global() {
+            self.exhausted
+                .borrow_mut()
+                .get_or_insert((instance.def_id(), exceeded));
+        }
+        self.exceeded
+            .borrow_mut()
+            .entry(instance.def_id())
+            .or_insert(exceeded);
+    }
+
+    /// Warn about every function whose analysis was cut short.
+    ///
+    /// Once a limit on the whole analysis is reached every function left is cut short,
+    /// so only the one which was being analyzed at the time gets a warning.
+    pub fn emit_warnings(&self, tcx: TyCtxt<'_>) {
+        let exceeded = self.exceeded.borrow();
+        for (&id, exceeded) in exceeded.iter().filter(|(_, e)| !e.is_global()) {
+            let name = tcx.def_path_str(id);
+            let message = match exceeded {
+                Exceeded::Blocks(max) => format!(
+                    "the taint analysis of `{}` visited more than {} basic blocks",
+                    name, max
+                ),
+                Exceeded::Depth(max) => format!(
+                    "the taint analysis of `{}` was nested in {} other analyses",
+                    name, max
+                ),
+                Exceeded::TotalBlocks(_) | Exceeded::Timeout(_) => continue,
+            };
+            tcx.sess
+                .struct_span_warn(tcx.def_span(id), message)
+                .note(CONSERVATIVE)
+                .emit();
+        }
+
+        let Some((id, limit)) = *self.exhausted.borrow() else {
+            return;
+        };
+        let name = tcx.def_path_str(id);
+        let message = match limit {
+            Exceeded::TotalBlocks(max) => format!(
+                "the taint analysis visited more than {} basic blocks in total while analyzing `{}`",
+                max, name
+            ),
+            Exceeded::Timeout(timeout) => format!(
+                "the taint analysis ran out of time after {} seconds while analyzing `{}`",
+                timeout, name
+            ),
+            Exceeded::Blocks(_) | Exceeded::Depth(_) => unreachable!(),
+        };
+        let mut warning = tcx.sess.struct_span_warn(tcx.def_span(id), message);
+        warning.note(CONSERVATIVE);
+        let others = exceeded
+            .iter()
+            .filter(|&(&other, e)| other != id && e.is_global())
+            .count();
+        if others > 0 {
+            warning.note(format!(
+                "{} other functions were not analyzed either",
+                others
+            ));
+        }
+        warning.emit();
+    }
+}
@@ -0,0 +1,118 @@
+//! The calls between the functions we analyze, which decide the order in which they are summarized.
+
+use std::collections::HashMap;
+
+use rustc_data_structures::graph::{scc::Sccs, vec_graph::VecGraph};
+use rustc_middle::{
+    mir::{traversal, Operand, TerminatorKind},
+    ty::{Instance, InstanceDef, TyCtxt},
+};
+
+use super::{
+    models::Model,
+    taint_analysis::{annotated, is_analyzable, resolve_callee, TaintEnv},
+};
+
+/// The functions reachable from a set of roots, and the calls between them.
+///
+/// Only calls to functions whose bodies we analyze are included,
+/// so calls to sources, sinks, sanitizers and modeled functions are left out.
+pub(crate) struct CallGraph<'tcx> {
+    /// The roots come first, followed by the functions they reach in the order they were found.
+    pub(crate) nodes: Vec<Instance<'tcx>>,
+    pub(crate) roots: usize,
+    /// Each call from one node to another, by their indices.
+    pub(crate) edges: Vec<(usize, usize)>,
+    index: HashMap<Instance<'tcx>, usize>,
+}
+
+impl<'tcx> CallGraph<'tcx> {
+    pub(crate) fn new(tcx: TyCtxt<'tcx>, env: &TaintEnv, roots: &[Instance<'tcx>]) -> Self {
+        let mut graph = CallGraph {
+            nodes: Vec::new(),
+            roots: 0,
+            edges: Vec::new(),
+            index: HashMap::new(),
+        };
+        for &root in roots {
+            graph.node(root);
+        }
+        graph.roots = graph.nodes.len();
+
+        let mut next = 0;
+        while next < graph.nodes.len() {
+            let caller = graph.nodes[next];
+            let body = tcx.instance_mir(caller.def);
+
+            for (_, data) in traversal::reachable(body) {
+                let TerminatorKind::Call {
+                    func: Operand::Constant(ref func),
+                    ..
+                } = data.terminator().kind
+                else {
+                    continue;
+                };
+
+                let (id, callee) = resolve_callee(tcx, caller, func);
+                let id = annotated(tcx, &env.info, id, callee);
+                let Some(callee) = callee else {
+                    continue;
+                };
+                if env.info.get_kind(&id).is_some()
+                    || Model::of(tcx, id).is_some()
+                    || !matches!(callee.def, InstanceDef::Item(_))
+                    || !is_analyzable(tcx, callee)
+                {
+                    continue;
+                }
+
+                let callee = graph.node(callee);
+                graph.edges.push((next, callee));
+            }
+            next += 1;
+        }
+
+        graph
+    }
+
+    fn node(&mut self, instance: Instance<'tcx>) -> usize {
+        let nodes = &mut self.nodes;
+        *self.index.entry(instance).or_insert_with(|| {
+            nodes.push(instance);
+            nodes.len() - 1
+        })
+    }
+
+    /// The groups of mutually recursive functions, by level, such that each group only calls into its own or earlier levels.
+    /// The groups within a level do not call each other, so they can be summarized independently.
+    pub(crate) fn levels(&self) -> Vec<Vec<Vec<usize>>> {
+        let graph = VecGraph::new(self.nodes.len(), self.edges.clone());
+        let sccs = Sccs::<usize, usize>::new(&graph);
+
+        let mut members = vec![Vec::new(); sccs.num_sccs()];
+        for node in 0..self.nodes.len() {
+            members[sccs.scc(node)].push(node);
+        }
+
+        // The groups are numbered such that the groups a group calls into come first.
+        let mut depth = vec![0; sccs.num_sccs()];
+        let mut levels: Vec<Vec<Vec<usize>>> = Vec::new();
+        for scc in sccs.all_sccs() {
+            let level = sccs
+                .successors(scc)
+                .iter()
+                .filter(|&&callee| callee != scc)
+                .map(|&callee| depth[callee] + 1)
+                .max()
+                .unwrap_or(0);
+            depth[scc] = level;
+
+            if levels.len() <= level {
+                levels.resize(level + 1, Vec::new());
+            }
+            levels[level].push(std::mem::take(&mut members[scc]));
+        }
+
+        levels
+    }
+}
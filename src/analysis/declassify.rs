@@ -0,0 +1,72 @@
+//! The places where values are declassified with `taint::declassify!`, which can be written out to audit them.
+//!
+//! Given `--taint-report-declassifications=<file>`, every call to `declassify!` in the crate is written as JSON,
+//! with the function it is in, its reason, and whether the analysis found a tainted value declassified there.
+//! A call in a function which was never analyzed is still listed, as not tainted.
+
+use std::{fs, path::PathBuf};
+
+use rustc_data_structures::{fx::FxHashSet, sync::Lock};
+use rustc_middle::ty::TyCtxt;
+use rustc_span::Span;
+
+use crate::eval::{
+    config::TaintConfig,
+    report::{escape, format_span, json_array},
+};
+
+use super::models::{annotation_calls, Model};
+
+#[derive(Debug)]
+pub struct Declassifications {
+    path: Option<PathBuf>,
+    /// The calls to `declassify!` which were given a tainted value, by the place of the call.
+    tainted: Lock<FxHashSet<Span>>,
+}
+
+impl Declassifications {
+    /// Nothing is recorded unless the declassifications are to be written out.
+    pub fn new(config: &TaintConfig) -> Self {
+        Declassifications {
+            path: config.declassifications.clone(),
+            tainted: Lock::new(FxHashSet::default()),
+        }
+    }
+
+    /// Record that a value was declassified, and whether it was tainted.
+    pub(crate) fn record(&self, span: Span, tainted: bool) {
+        if self.path.is_some() && tainted {
+            self.tainted.borrow_mut().insert(span.source_callsite());
+        }
+    }
+
+    /// Write every call to `declassify!` in the crate, whether or not the analysis reached it.
+    pub fn store(&self, tcx: TyCtxt<'_>) {
+        let Some(path) = &self.path else {
+            return;
+        };
+
+        let tainted = self.tainted.borrow();
+        let sites = annotation_calls(tcx)
+            .iter()
+            .filter(|call| call.model == Model::Declassify)
+            .map(|call| {
+                format!(
+                    "    {{\"function\": \"{}\", \"span\": \"{}\", \"reason\": \"{}\", \"tainted\": {}}}",
+                    escape(&tcx.def_path_str(call.function.to_def_id())),
+                    escape(&format_span(tcx, call.span)),
+                    escape(&call.text),
+                    tainted.contains(&call.span)
+                )
+            })
+            .collect::<Vec<_>>();
+        let contents = format!("{{\n  \"declassifications\": {}\n}}\n", json_array(&sites));
+        if let Err(e) = fs::write(path, contents) {
+            tcx.sess.warn(format!(
+                "could not write the declassifications to `{}`: {}",
+                path.display(),
+                e
+            ));
+        }
+    }
+}
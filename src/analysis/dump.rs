@@ -0,0 +1,171 @@
+//! Writes the MIR of a function with the taint of its locals around each statement, to see where taint appears or is dropped.
+//!
+//! Given `--taint-dump-mir=<fn-path>`, every context the function is analyzed in is written to
+//! `<fn-path>.<context>.taint.mir` in the output directory, where the context is the taint of its arguments (`1` if tainted).
+//! Each block starts with the tainted locals and the aliases on entry, and each statement and terminator is followed by those after it.
+//! The taint a call returns is applied on the edge to its return block, so it only shows at the start of that block.
+//! The analysis does not keep the aliases of each statement, but only adds to them as it goes,
+//! so the aliases shown are those found by the statements visited so far, in the order the blocks are visited.
+
+use std::{
+    collections::BTreeSet,
+    fmt::Write as _,
+    fs,
+};
+
+use rustc_data_structures::sync::Lock;
+use rustc_index::bit_set::BitSet;
+use rustc_middle::{
+    mir::{BasicBlock, BasicBlockData, Body, Local, Location, Statement, Terminator},
+    ty::{Instance, TyCtxt},
+};
+use rustc_mir_dataflow::{Results, ResultsVisitor};
+
+use crate::eval::config::TaintConfig;
+
+use super::taint_analysis::{InitSet, PointsMap, TaintAnalysis};
+
+#[derive(Debug)]
+pub struct MirDump {
+    path: Option<String>,
+    /// Whether the function was analyzed at all.
+    found: Lock<bool>,
+}
+
+impl MirDump {
+    pub fn new(config: &TaintConfig) -> Self {
+        MirDump {
+            path: config.dump_mir.clone(),
+            found: Lock::new(false),
+        }
+    }
+
+    /// Whether the analysis of `instance` is to be written out.
+    pub(crate) fn wants(&self, tcx: TyCtxt<'_>, instance: Instance<'_>) -> bool {
+        self.path
+            .as_ref()
+            .map_or(false, |path| *path == tcx.def_path_str(instance.def_id()))
+    }
+
+    /// Write the MIR of a function with the taint computed for it in one context.
+    ///
+    /// The results are visited again to get the state at each statement,
+    /// which records nothing, such as the sinks it reaches, a second time.
+    pub(crate) fn write<'tcx>(
+        &self,
+        tcx: TyCtxt<'tcx>,
+        (instance, init): (Instance<'tcx>, &InitSet),
+        body: &Body<'tcx>,
+        results: &mut Results<'tcx, TaintAnalysis<'tcx, '_>>,
+    ) {
+        *self.found.borrow_mut() = true;
+        let path = self.path.as_deref().unwrap_or_default();
+        let context = init
+            .iter()
+            .map(|&t| if t == Some(true) { '1' } else { '0' })
+            .collect::<String>();
+
+        let mut writer = MirWriter {
+            out: format!(
+                "// The taint analysis of `{}` with arguments ({}).\n",
+                instance, context
+            ),
+        };
+        results.visit_reachable_with(body, &mut writer);
+
+        let file = format!(
+            "{}.{}.taint.mir",
+            path.replace(|c: char| !c.is_alphanumeric() && c != '_', "_"),
+            context
+        );
+        let file = tcx.output_filenames(()).out_directory.join(file);
+        if let Err(e) = fs::write(&file, writer.out) {
+            tcx.sess.warn(format!(
+                "could not write the MIR of `{}` to `{}`: {}",
+                path,
+                file.display(),
+                e
+            ));
+        }
+    }
+
+    /// Warn if the function to write out was never analyzed, e.g. because its path was misspelled.
+    pub fn finish(&self, tcx: TyCtxt<'_>) {
+        if let Some(path) = &self.path {
+            if !*self.found.borrow() {
+                tcx.sess.warn(format!(
+                    "`{}` was not analyzed, so its MIR was not written",
+                    path
+                ));
+            }
+        }
+    }
+}
+
+struct MirWriter {
+    out: String,
+}
+
+impl MirWriter {
+    fn state(&mut self, state: &BitSet<Local>, points: &PointsMap) {
+        let _ = writeln!(self.out, "        // tainted: {}", format_locals(state.iter()));
+        let mut aliases = points.iter().collect::<Vec<_>>();
+        aliases.sort_by_key(|(local, _)| **local);
+        let aliases = aliases
+            .into_iter()
+            .map(|(local, targets)| {
+                format!("{:?} -> {}", local, format_locals(targets.iter().copied()))
+            })
+            .collect::<Vec<_>>();
+        let _ = writeln!(self.out, "        // aliases: {{{}}}", aliases.join(", "));
+    }
+}
+
+impl<'mir, 'tcx, 'inter> ResultsVisitor<'mir, 'tcx, Results<'tcx, TaintAnalysis<'tcx, 'inter>>>
+    for MirWriter
+{
+    type FlowState = BitSet<Local>;
+
+    fn visit_block_start(
+        &mut self,
+        results: &Results<'tcx, TaintAnalysis<'tcx, 'inter>>,
+        state: &Self::FlowState,
+        _block_data: &'mir BasicBlockData<'tcx>,
+        block: BasicBlock,
+    ) {
+        let _ = writeln!(self.out, "\n    {:?}: {{", block);
+        self.state(state, &results.analysis.points());
+    }
+
+    fn visit_statement_after_primary_effect(
+        &mut self,
+        results: &Results<'tcx, TaintAnalysis<'tcx, 'inter>>,
+        state: &Self::FlowState,
+        statement: &'mir Statement<'tcx>,
+        _location: Location,
+    ) {
+        let _ = writeln!(self.out, "        {:?};", statement);
+        self.state(state, &results.analysis.points());
+    }
+
+    fn visit_terminator_after_primary_effect(
+        &mut self,
+        results: &Results<'tcx, TaintAnalysis<'tcx, 'inter>>,
+        state: &Self::FlowState,
+        terminator: &'mir Terminator<'tcx>,
+        _location: Location,
+    ) {
+        let _ = writeln!(self.out, "        {:?};", terminator.kind);
+        self.state(state, &results.analysis.points());
+        self.out.push_str("    }\n");
+    }
+}
+
+fn format_locals(locals: impl Iterator<Item = Local>) -> String {
+    let locals = locals.collect::<BTreeSet<_>>();
+    let locals = locals
+        .iter()
+        .map(|local| format!("{:?}", local))
+        .collect::<Vec<_>>();
+    format!("{{{}}}", locals.join(", "))
+}
@@ -0,0 +1,205 @@
+//! The call graph discovered by the analysis, which can be written out to see why a sink was or was not reached.
+//!
+//! Given `--taint-call-graph=<file>`, it is written in JSON if the file ends in `.json`, and in Graphviz DOT otherwise.
+//! Each function is listed with the contexts it was analyzed in, i.e. the taint of its arguments (`1` if tainted),
+//! and each call with the contexts of the caller and the callee it was analyzed in.
+//! A call carries taint if one of its arguments was tainted.
+
+use std::{
+    collections::{BTreeMap, BTreeSet},
+    fs,
+    path::PathBuf,
+};
+
+use rustc_data_structures::sync::Lock;
+use rustc_middle::ty::{Instance, TyCtxt};
+use rustc_span::Span;
+
+use crate::eval::{
+    config::TaintConfig,
+    report::{escape, format_span, json_array},
+};
+
+use super::taint_analysis::InitSet;
+
+#[derive(Debug, Default)]
+struct Function {
+    contexts: BTreeSet<String>,
+    sink: bool,
+}
+
+/// The calls from one place in a caller to one callee.
+#[derive(Debug, Default)]
+struct Call {
+    /// The contexts of the caller, and of the callee it called in that context.
+    contexts: BTreeSet<(String, String)>,
+    tainted: bool,
+    returns_taint: bool,
+}
+
+#[derive(Debug)]
+pub struct CallGraphExport {
+    path: Option<PathBuf>,
+    functions: Lock<BTreeMap<String, Function>>,
+    /// The calls, by caller, callee and the place of the call.
+    calls: Lock<BTreeMap<(String, String, String), Call>>,
+}
+
+impl CallGraphExport {
+    /// Nothing is recorded unless the call graph is to be written out.
+    pub fn new(config: &TaintConfig) -> Self {
+        CallGraphExport {
+            path: config.call_graph.clone(),
+            functions: Lock::new(BTreeMap::new()),
+            calls: Lock::new(BTreeMap::new()),
+        }
+    }
+
+    /// Record that a function was analyzed with the given taint of its arguments.
+    pub(crate) fn function(&self, instance: Instance<'_>, init: &InitSet) {
+        if self.path.is_some() {
+            self.functions
+                .borrow_mut()
+                .entry(instance.to_string())
+                .or_default()
+                .contexts
+                .insert(format_init(init));
+        }
+    }
+
+    /// Record a call to an analyzed function, or to a sink if `returns_taint` is `None`.
+    pub(crate) fn call(
+        &self,
+        tcx: TyCtxt<'_>,
+        (caller, caller_init): (Instance<'_>, &InitSet),
+        (callee, callee_init): (String, &InitSet),
+        span: Span,
+        returns_taint: Option<bool>,
+    ) {
+        if self.path.is_none() {
+            return;
+        }
+
+        let mut functions = self.functions.borrow_mut();
+        let function = functions.entry(callee.clone()).or_default();
+        function.sink |= returns_taint.is_none();
+        if function.sink {
+            function.contexts.insert(format_init(callee_init));
+        }
+
+        let place = format_span(tcx, span);
+        let mut calls = self.calls.borrow_mut();
+        let call = calls
+            .entry((caller.to_string(), callee, place))
+            .or_default();
+        call.contexts
+            .insert((format_init(caller_init), format_init(callee_init)));
+        call.tainted |= callee_init.iter().any(|&t| t == Some(true));
+        call.returns_taint |= returns_taint.unwrap_or(false);
+    }
+
+    pub fn store(&self, tcx: TyCtxt<'_>) {
+        let Some(path) = &self.path else {
+            return;
+        };
+
+        let contents = if path.extension().map_or(false, |ext| ext == "json") {
+            self.to_json()
+        } else {
+            self.to_dot()
+        };
+        if let Err(e) = fs::write(path, contents) {
+            tcx.sess.warn(format!(
+                "could not write the call graph to `{}`: {}",
+                path.display(),
+                e
+            ));
+        }
+    }
+
+    fn to_dot(&self) -> String {
+        let mut dot = String::from("digraph taint {\n");
+        for (name, function) in self.functions.borrow().iter() {
+            let mut label = escape(name);
+            for context in &function.contexts {
+                label.push_str(&format!("\\n({})", context));
+            }
+            dot.push_str(&format!(
+                "    \"{}\" [label=\"{}\"{}];\n",
+                escape(name),
+                label,
+                if function.sink { ", shape=box" } else { "" }
+            ));
+        }
+        for ((caller, callee, place), call) in self.calls.borrow().iter() {
+            let mut label = escape(place);
+            for (from, to) in &call.contexts {
+                label.push_str(&format!("\\n({}) -> ({})", from, to));
+            }
+            dot.push_str(&format!(
+                "    \"{}\" -> \"{}\" [label=\"{}\"{}{}];\n",
+                escape(caller),
+                escape(callee),
+                label,
+                if call.tainted { ", color=red" } else { "" },
+                if call.returns_taint { ", style=bold" } else { "" }
+            ));
+        }
+        dot.push_str("}\n");
+        dot
+    }
+
+    fn to_json(&self) -> String {
+        let functions = self
+            .functions
+            .borrow()
+            .iter()
+            .map(|(name, function)| {
+                format!(
+                    "    {{\"name\": \"{}\", \"sink\": {}, \"contexts\": [{}]}}",
+                    escape(name),
+                    function.sink,
+                    function
+                        .contexts
+                        .iter()
+                        .map(|c| format!("\"{}\"", c))
+                        .collect::<Vec<_>>()
+                        .join(", ")
+                )
+            })
+            .collect::<Vec<_>>();
+        let calls = self
+            .calls
+            .borrow()
+            .iter()
+            .map(|((caller, callee, place), call)| {
+                format!(
+                    "    {{\"caller\": \"{}\", \"callee\": \"{}\", \"span\": \"{}\", \"tainted\": {}, \"returns_taint\": {}, \"contexts\": [{}]}}",
+                    escape(caller),
+                    escape(callee),
+                    escape(place),
+                    call.tainted,
+                    call.returns_taint,
+                    call.contexts
+                        .iter()
+                        .map(|(from, to)| format!("[\"{}\", \"{}\"]", from, to))
+                        .collect::<Vec<_>>()
+                        .join(", ")
+                )
+            })
+            .collect::<Vec<_>>();
+
+        format!(
+            "{{\n  \"functions\": {},\n  \"calls\": {}\n}}\n",
+            json_array(&functions),
+            json_array(&calls)
+        )
+    }
+}
+
+/// The taint of each argument, as in the summaries on disk.
+fn format_init(init: &InitSet) -> String {
+    init.iter()
+        .map(|&t| if t == Some(true) { '1' } else { '0' })
+        .collect()
+}
@@ -0,0 +1,418 @@
+//! A cache of function summaries which is kept between runs when the crate is compiled incrementally,
+//! so that only the functions which changed, and the functions which call them, are analyzed again.
+//!
+//! Every entry records the definitions in the current crate its summary depends on, such as the bodies of its callees,
+//! along with a fingerprint of each. An entry is only used if none of them have changed since.
+//! Anything else a summary depends on, such as the marked functions and the builds and summaries of our dependencies,
+//! is part of a fingerprint of the whole cache, which is discarded if that changes.
+//!
+//! The cache is plain text, with one tab-separated field per line, and an `entry` line starting each entry:
+//!
+//! ```text
+//! entry    <key>
+//! exit     <normal> <unwind> <tainted panic>
+//! return   <global>...
+//! sink     <name> [<label>]
+//! finding  <file> <lo> <hi> <name> [<label>]
+//! global   <global>
+//! dep      <def path hash> <fingerprint>
+//! ```
+
+use std::{
+    collections::{BTreeMap, BTreeSet, HashMap},
+    fs,
+    hash::Hash,
+    path::PathBuf,
+};
+
+use rustc_data_structures::{
+    fingerprint::Fingerprint,
+    stable_hasher::{HashStable, StableHasher},
+    sync::Lock,
+};
+use rustc_hir::def::DefKind;
+use rustc_hir::def_id::{DefId, DefPathHash, LOCAL_CRATE};
+use rustc_middle::ty::{Instance, InstanceDef, TyCtxt, TyKind};
+use rustc_span::{BytePos, Span};
+
+use crate::eval::{attributes::AttrInfo, config::TaintConfig};
+
+use super::{
+    summaries::{
+        format_exit, format_fingerprint, format_hash, format_loc, parse_exit, parse_hash,
+        parse_loc, ExternDefs, ExternSummaries,
+    },
+    taint_analysis::{ContextKey, Effects, Finding, GlobalLoc, Globals, Summary},
+};
+
+const HEADER: &str = "taint-cache 1";
+
+/// Summaries from the previous run, and the ones to keep for the next.
+#[derive(Debug, Default)]
+pub struct IncrementalCache {
+    /// Where the cache is kept, if the crate is compiled incrementally.
+    path: Option<PathBuf>,
+    /// The first line of the cache, with the fingerprint of everything besides the definitions in the current crate.
+    header: String,
+    /// The entries of the previous run, by the fingerprint of their key.
+    old: HashMap<String, String>,
+    /// The entries which are still valid or were added in this run, possibly from several threads.
+    new: Lock<BTreeMap<String, String>>,
+    /// The definitions in the current crate, which are looked up by their hash.
+    local_defs: HashMap<DefPathHash, DefId>,
+    /// The definitions of the dependencies which globals in the cache belong to, once they are looked up.
+    extern_defs: Lock<ExternDefs>,
+    fingerprints: Lock<HashMap<DefId, Fingerprint>>,
+}
+
+impl IncrementalCache {
+    pub fn load(
+        tcx: TyCtxt<'_>,
+        info: &AttrInfo,
+        config: &TaintConfig,
+        summaries: &ExternSummaries,
+    ) -> Self {
+        let Some(dir) = &tcx.sess.opts.incremental else {
+            return IncrementalCache::default();
+        };
+        // The call graph, the MIR and the declassifications are written as functions are analyzed,
+        // so none of them may be skipped.
+        if config.call_graph.is_some()
+            || config.dump_mir.is_some()
+            || config.declassifications.is_some()
+        {
+            return IncrementalCache::default();
+        }
+        let path = dir.join(format!(
+            "taint-{}-{}.cache",
+            tcx.crate_name(LOCAL_CRATE),
+            tcx.stable_crate_id(LOCAL_CRATE).as_u64()
+        ));
+
+        let mut hasher = StableHasher::new();
+        HEADER.hash(&mut hasher);
+        format!("{:?}", config).hash(&mut hasher);
+        for names in [&info.labels, &info.kinds] {
+            let mut names = names
+                .iter()
+                .map(|(&id, name)| (format_hash(tcx.def_path_hash(id)), name))
+                .collect::<Vec<_>>();
+            names.sort();
+            names.hash(&mut hasher);
+        }
+        for ids in [
+            &info.sources,
+            &info.sinks,
+            &info.sanitizers,
+            &info.none,
+            &info.tainted_types,
+            &info.sink_types,
+        ] {
+            let mut hashes = ids
+                .iter()
+                .map(|&id| format_hash(tcx.def_path_hash(id)))
+                .collect::<Vec<_>>();
+            hashes.sort();
+            hashes.hash(&mut hasher);
+        }
+        summaries.fingerprint().hash(&mut hasher);
+        // The definitions of our dependencies, and so their hashes, change whenever they are rebuilt.
+        for &cnum in tcx.crates(()) {
+            tcx.crate_hash(cnum).hash(&mut hasher);
+        }
+        let header = format!("{}\t{}", HEADER, format_fingerprint(hasher.finish()));
+
+        let mut old = HashMap::new();
+        if let Ok(contents) = fs::read_to_string(&path) {
+            let mut lines = contents.lines();
+            if lines.next() == Some(&header) {
+                let mut key = None;
+                for line in lines {
+                    if let Some(k) = line.strip_prefix("entry\t") {
+                        key = Some(k.to_owned());
+                    } else if let Some(key) = &key {
+                        let entry: &mut String = old.entry(key.clone()).or_default();
+                        entry.push_str(line);
+                        entry.push('\n');
+                    }
+                }
+            }
+        }
+
+        let local_defs = tcx
+            .iter_local_def_id()
+            .map(|id| (tcx.def_path_hash(id.to_def_id()), id.to_def_id()))
+            .collect();
+
+        IncrementalCache {
+            path: Some(path),
+            header,
+            old,
+            new: Lock::new(BTreeMap::new()),
+            local_defs,
+            extern_defs: Lock::new(ExternDefs::default()),
+            fingerprints: Lock::new(HashMap::new()),
+        }
+    }
+
+    /// The fingerprint of what a summary is computed from, besides the definitions it depends on.
+    pub(crate) fn key(
+        &self,
+        tcx: TyCtxt<'_>,
+        (instance, init, init_refs, calls, receiver): &ContextKey<'_>,
+        globals: &Globals,
+    ) -> Option<String> {
+        self.path.as_ref()?;
+
+        let mut hasher = StableHasher::new();
+        tcx.with_stable_hashing_context(|mut hcx| instance.hash_stable(&mut hcx, &mut hasher));
+        init.hash(&mut hasher);
+        for refs in init_refs {
+            refs.iter()
+                .map(|&loc| format_loc(tcx, loc))
+                .collect::<Vec<_>>()
+                .hash(&mut hasher);
+        }
+        for (caller, location) in calls {
+            tcx.with_stable_hashing_context(|mut hcx| caller.hash_stable(&mut hcx, &mut hasher));
+            (location.block.as_u32(), location.statement_index).hash(&mut hasher);
+        }
+        if let Some((owner, locals)) = receiver {
+            tcx.with_stable_hashing_context(|mut hcx| owner.hash_stable(&mut hcx, &mut hasher));
+            locals
+                .iter()
+                .map(|local| local.as_u32())
+                .collect::<Vec<_>>()
+                .hash(&mut hasher);
+        }
+        let mut globals = globals
+            .iter()
+            .map(|&loc| format_loc(tcx, loc))
+            .collect::<Vec<_>>();
+        globals.sort();
+        globals.hash(&mut hasher);
+
+        Some(format_fingerprint(hasher.finish()))
+    }
+
+    /// Get the summary from the previous run, if nothing it depends on has changed.
+    pub(crate) fn get(&self, tcx: TyCtxt<'_>, key: &str) -> Option<Summary> {
+        let entry = self.old.get(key)?;
+        let summary = self.parse(tcx, entry)?;
+        self.new.borrow_mut().insert(key.to_owned(), entry.clone());
+        Some(summary)
+    }
+
+    fn parse(&self, tcx: TyCtxt<'_>, entry: &str) -> Option<Summary> {
+        let mut summary = Summary {
+            normal: None,
+            unwind: None,
+            tainted_panic: false,
+            return_refs: BTreeSet::new(),
+            effects: Effects::default(),
+        };
+
+        for line in entry.lines() {
+            let mut fields = line.split('\t');
+            match fields.next()? {
+                "exit" => {
+                    summary.normal = parse_exit(fields.next()?)?;
+                    summary.unwind = parse_exit(fields.next()?)?;
+                    summary.tainted_panic = fields.next()? == "1";
+                }
+                "return" => {
+                    for loc in fields {
+                        summary.return_refs.insert(self.parse_loc(tcx, loc)?);
+                    }
+                }
+                "sink" => {
+                    let name = fields.next()?.to_owned();
+                    let label = fields.next().map(str::to_owned);
+                    summary.effects.sinks.insert((name, label));
+                }
+                "finding" => {
+                    let file = fields.next()?;
+                    let lo = fields.next()?.parse().ok()?;
+                    let hi = fields.next()?.parse().ok()?;
+                    let name = fields.next()?.to_owned();
+                    let label = fields.next().map(str::to_owned);
+
+                    let file = tcx
+                        .sess
+                        .source_map()
+                        .files()
+                        .iter()
+                        .find(|f| f.name.prefer_local().to_string() == file)?
+                        .clone();
+                    let span = Span::with_root_ctxt(
+                        file.start_pos + BytePos(lo),
+                        file.start_pos + BytePos(hi),
+                    );
+                    summary
+                        .effects
+                        .findings
+                        .push(Finding { name, label, span });
+                }
+                "global" => {
+                    summary
+                        .effects
+                        .globals
+                        .insert(self.parse_loc(tcx, fields.next()?)?);
+                }
+                "dep" => {
+                    let id = *self.local_defs.get(&parse_hash(fields.next()?)?)?;
+                    if format_fingerprint(self.fingerprint(tcx, id)) != fields.next()? {
+                        return None;
+                    }
+                    summary.effects.deps.insert(id);
+                }
+                _ => return None,
+            }
+        }
+
+        Some(summary)
+    }
+
+    /// Keep a summary for the next run.
+    pub(crate) fn insert(&self, tcx: TyCtxt<'_>, key: String, arg_count: usize, summary: &Summary) {
+        // A summary cut short by the budget may be completed by a run with more time.
+        if summary.effects.partial {
+            return;
+        }
+
+        // Findings in macro expansions cannot be restored with their expansion, so they are always found again.
+        if summary
+            .effects
+            .findings
+            .iter()
+            .any(|f| f.span.from_expansion())
+        {
+            return;
+        }
+
+        let mut lines = vec![format!(
+            "exit\t{}\t{}\t{}",
+            format_exit(&summary.normal, arg_count),
+            format_exit(&summary.unwind, arg_count),
+            if summary.tainted_panic { "1" } else { "0" }
+        )];
+        if !summary.return_refs.is_empty() {
+            let refs = summary.return_refs.iter().map(|&loc| format_loc(tcx, loc));
+            lines.push(
+                std::iter::once("return".to_owned())
+                    .chain(refs)
+                    .collect::<Vec<_>>()
+                    .join("\t"),
+            );
+        }
+        for (name, label) in &summary.effects.sinks {
+            lines.push(format!("sink\t{}{}", name, format_label(label)));
+        }
+        for Finding { name, label, span } in &summary.effects.findings {
+            let file = tcx.sess.source_map().lookup_source_file(span.lo());
+            lines.push(format!(
+                "finding\t{}\t{}\t{}\t{}{}",
+                file.name.prefer_local(),
+                (span.lo() - file.start_pos).0,
+                (span.hi() - file.start_pos).0,
+                name,
+                format_label(label)
+            ));
+        }
+        for &loc in &summary.effects.globals {
+            lines.push(format!("global\t{}", format_loc(tcx, loc)));
+        }
+        for &id in &summary.effects.deps {
+            lines.push(format!(
+                "dep\t{}\t{}",
+                format_hash(tcx.def_path_hash(id)),
+                format_fingerprint(self.fingerprint(tcx, id))
+            ));
+        }
+
+        self.new.borrow_mut().insert(key, lines.join("\n") + "\n");
+    }
+
+    pub fn store(&self, tcx: TyCtxt<'_>) {
+        let Some(path) = &self.path else {
+            return;
+        };
+
+        let mut contents = format!("{}\n", self.header);
+        for (key, entry) in self.new.borrow().iter() {
+            contents.push_str(&format!("entry\t{}\n{}", key, entry));
+        }
+
+        if let Err(e) = fs::write(path, contents) {
+            tcx.sess.warn(format!(
+                "could not write the taint cache to `{}`: {}",
+                path.display(),
+                e
+            ));
+        }
+    }
+
+    /// The fingerprint of a function body, or of the fields of a type.
+    fn fingerprint(&self, tcx: TyCtxt<'_>, id: DefId) -> Fingerprint {
+        if let Some(&fingerprint) = self.fingerprints.borrow().get(&id) {
+            return fingerprint;
+        }
+
+        // The lock is not held while hashing, since the queries we run may wait on other threads.
+        let mut hasher = StableHasher::new();
+        tcx.with_stable_hashing_context(|mut hcx| match tcx.def_kind(id) {
+            DefKind::Struct | DefKind::Enum | DefKind::Union => {
+                let adt = tcx.adt_def(id);
+                adt.hash_stable(&mut hcx, &mut hasher);
+                for field in adt.all_fields() {
+                    tcx.type_of(field.did)
+                        .skip_binder()
+                        .hash_stable(&mut hcx, &mut hasher);
+                }
+            }
+            _ => tcx.optimized_mir(id).hash_stable(&mut hcx, &mut hasher),
+        });
+        let fingerprint = hasher.finish();
+        self.fingerprints.borrow_mut().insert(id, fingerprint);
+        fingerprint
+    }
+
+    fn parse_loc(&self, tcx: TyCtxt<'_>, s: &str) -> Option<GlobalLoc> {
+        parse_loc(s, |hash| {
+            if hash.stable_crate_id() == tcx.stable_crate_id(LOCAL_CRATE) {
+                self.local_defs.get(&hash).copied()
+            } else {
+                self.extern_defs.borrow_mut().get(tcx, hash)
+            }
+        })
+
+    }
+}
+
+/// The definitions in the current crate whose changes may change the summary of a function,
+/// besides those of its callees.
+pub(crate) fn body_deps(instance: Instance<'_>) -> Vec<DefId> {
+    match instance.def {
+        InstanceDef::Item(id) if id.is_local() => vec![id],
+        // Drop glue is generated from the fields of the dropped type.
+        InstanceDef::DropGlue(_, Some(ty)) => ty
+            .walk()
+            .filter_map(|arg| arg.as_type())
+            .filter_map(|ty| match *ty.kind() {
+                TyKind::Adt(adt, _) => Some(adt.did()),
+                TyKind::Closure(id, _) | TyKind::Generator(id, _, _) => Some(id),
+                _ => None,
+            })
+            .filter(|id| id.is_local())
+            .collect(),
+        _ => vec![],
+    }
+}
+
+
+/// The label of a sink as an optional last field.
+fn format_label(label: &Option<String>) -> String {
+    label
+        .as_ref()
+        .map_or_else(String::new, |label| format!("\t{}", label))
+}
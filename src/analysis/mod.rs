@@ -1,3 +1,23 @@
 pub mod taint_analysis;
 
+pub mod budget;
+
+pub mod export;
+
+pub mod dump;
+
+pub mod declassify;
+
+pub mod assertions;
+
+mod call_graph;
+
+mod models;
+
+mod incremental;
+
+pub(crate) mod scheduler;
+
+pub mod summaries;
+
 mod taint_domain;
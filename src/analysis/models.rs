@@ -0,0 +1,177 @@
+//! Models for standard library functions whose effect on taint is not visible from their arguments alone,
+//! and for the functions of the `taint-annotations` crate.
+
+use rustc_hir::def::DefKind;
+use rustc_hir::def_id::{DefId, LocalDefId};
+use rustc_middle::{
+    mir::{
+        interpret::{ConstValue, GlobalAlloc, Scalar},
+        Constant, ConstantKind, Operand, TerminatorKind,
+    },
+    ty::{print::with_no_trimmed_paths, ParamEnv, TyCtxt, TyKind},
+};
+use rustc_span::{sym, Span};
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub(crate) enum Model {
+    /// Creates a value whose clones share the same contents, such as both ends of a channel.
+    Allocation,
+    /// Runs its closure argument, possibly on another thread, and catches any panic in it.
+    Spawn,
+    /// Runs its closure argument with a reference to the value of a thread local, given by its `LocalKey`.
+    LocalKey,
+    /// Unwinds with a payload made from its arguments.
+    Panic,
+    /// Unwraps its receiver, or panics with the message given as the last argument.
+    Expect,
+    /// Returns its first argument, which is no longer tainted (`taint::declassify!`).
+    Declassify,
+    /// Does nothing, but the analysis checks that the referenced value may be tainted (`taint::assert_tainted!`).
+    AssertTainted,
+    /// Does nothing, but the analysis checks that the referenced value is not tainted (`taint::assert_clean!`).
+    AssertClean,
+}
+
+impl Model {
+    pub(crate) fn of(tcx: TyCtxt<'_>, id: DefId) -> Option<Model> {
+        // The crate is usually imported under another name, which the path of the function would start with.
+        if tcx.crate_name(id.krate).as_str() == "taint_annotations" {
+            return match tcx.opt_item_name(id)?.as_str() {
+                "declassify" => Some(Model::Declassify),
+                "assert_tainted" => Some(Model::AssertTainted),
+                "assert_clean" => Some(Model::AssertClean),
+                _ => None,
+            };
+        }
+        match with_no_trimmed_paths!(tcx.def_path_str(id)).as_str() {
+            "std::sync::mpsc::channel"
+            | "std::sync::mpsc::sync_channel"
+            | "std::sync::Arc::<T>::new"
+            | "std::sync::Mutex::<T>::new"
+            | "std::sync::RwLock::<T>::new" => Some(Model::Allocation),
+            "std::thread::spawn"
+            | "std::thread::scope"
+            | "std::thread::Builder::spawn"
+            | "std::thread::Builder::spawn_scoped"
+            | "std::thread::Scope::<'scope, 'env>::spawn"
+            | "std::panic::catch_unwind" => Some(Model::Spawn),
+            "std::thread::LocalKey::<T>::with"
+            | "std::thread::LocalKey::<T>::try_with"
+            | "std::thread::LocalKey::<std::cell::RefCell<T>>::with_borrow"
+            | "std::thread::LocalKey::<std::cell::RefCell<T>>::with_borrow_mut" => {
+                Some(Model::LocalKey)
+            }
+            "std::rt::begin_panic"
+            | "std::rt::panic_fmt"
+            | "std::rt::panic_display"
+            | "std::panic::panic_any"
+            | "std::panic::resume_unwind" => Some(Model::Panic),
+            "std::option::Option::<T>::expect" | "std::result::Result::<T, E>::expect" => {
+                Some(Model::Expect)
+            }
+            _ => None,
+        }
+    }
+}
+
+/// A call to a function of the `taint-annotations` crate, in a function of the current crate.
+pub(crate) struct AnnotationCall {
+    pub(crate) model: Model,
+    /// Where the macro which expands to the call is used.
+    pub(crate) span: Span,
+    pub(crate) function: LocalDefId,
+    /// The string literal given as the last argument, i.e. the reason of a declassification or the asserted expression.
+    pub(crate) text: String,
+}
+
+/// Every call to a function of the `taint-annotations` crate in the current crate, in the order they appear in,
+/// whether or not the analysis reaches them.
+pub(crate) fn annotation_calls(tcx: TyCtxt<'_>) -> Vec<AnnotationCall> {
+    let mut calls = Vec::new();
+    if !tcx
+        .crates(())
+        .iter()
+        .any(|&krate| tcx.crate_name(krate).as_str() == "taint_annotations")
+    {
+        return calls;
+    }
+    for id in tcx.hir().body_owners() {
+        if !tcx.def_kind(id).is_fn_like() {
+            continue;
+        }
+        for block in tcx.optimized_mir(id).basic_blocks.iter() {
+            let TerminatorKind::Call { func, args, .. } = &block.terminator().kind else {
+                continue;
+            };
+            let Some(model) = func
+                .const_fn_def()
+                .and_then(|(callee, _)| Model::of(tcx, callee))
+            else {
+                continue;
+            };
+            if matches!(
+                model,
+                Model::Declassify | Model::AssertTainted | Model::AssertClean
+            ) {
+                calls.push(AnnotationCall {
+                    model,
+                    span: block.terminator().source_info.span.source_callsite(),
+                    function: id,
+                    text: args.last().and_then(string).unwrap_or_default(),
+                });
+            }
+        }
+    }
+    calls.sort_by_key(|call| call.span.lo());
+    calls.dedup_by_key(|call| call.span);
+    calls
+}
+
+/// The value of a string literal passed as an argument.
+fn string(operand: &Operand<'_>) -> Option<String> {
+    let constant = operand.constant()?;
+    let ConstantKind::Val(ConstValue::Slice { data, start, end }, _) = constant.literal else {
+        return None;
+    };
+    let bytes = data
+        .inner()
+        .inspect_with_uninit_and_ptr_outside_interpreter(start..end);
+    Some(String::from_utf8_lossy(bytes).into_owned())
+}
+
+/// The item declared with `thread_local!` which a constant reference to a `LocalKey` is the key of.
+///
+/// The key only holds a pointer to a function which the macro defines within the item, so we find the item through it.
+pub(crate) fn thread_local_key<'tcx>(
+    tcx: TyCtxt<'tcx>,
+    constant: &Constant<'tcx>,
+) -> Option<DefId> {
+    let TyKind::Ref(_, ty, _) = constant.ty().kind() else {
+        return None;
+    };
+    let TyKind::Adt(adt, _) = ty.kind() else {
+        return None;
+    };
+    if !tcx.is_diagnostic_item(sym::LocalKey, adt.did()) {
+        return None;
+    }
+
+    let Scalar::Ptr(ptr, _) = constant
+        .literal
+        .eval(tcx, ParamEnv::reveal_all())
+        .try_to_scalar()?
+    else {
+        return None;
+    };
+    let GlobalAlloc::Memory(key) = tcx.try_get_global_alloc(ptr.provenance)? else {
+        return None;
+    };
+    key.inner().provenance().ptrs().values().find_map(|&alloc| {
+        match tcx.try_get_global_alloc(alloc)? {
+            GlobalAlloc::Function(instance) => tcx
+                .opt_parent(instance.def_id())
+                .filter(|&item| tcx.def_kind(item) == DefKind::Const),
+            _ => None,
+        }
+    })
+}
@@ -0,0 +1,165 @@
+//! Summarizes the functions in the call graph bottom-up, so that the summaries of callees are ready when their callers are analyzed.
+//!
+//! The groups of functions in each level of the call graph do not call each other,
+//! so they are analyzed in parallel when the compiler was built with the parallel front-end
+//! and runs with more than one thread (`--taint-threads`). Otherwise they are analyzed one after another.
+//! Each group only sees the summaries and the tainted global memory from before its level started,
+//! and what it finds is combined with the rest once the whole level is done, in the order of the groups.
+//! This way the results do not depend on the number of threads, or on which group finishes first.
+
+use std::{cell::RefCell, collections::hash_map::Entry, rc::Rc};
+
+use rustc_data_structures::sync::par_map;
+use rustc_middle::ty::{Instance, TyCtxt};
+
+use super::{
+    call_graph::CallGraph,
+    taint_analysis::{
+        join_args, ContextStore, Contexts, Findings, Globals, Merged, TaintAnalysis, TaintEnv,
+    },
+};
+
+/// What the analysis of one group found.
+struct Outcome<'tcx> {
+    contexts: Contexts<'tcx>,
+    globals: Globals,
+    merged: Merged<'tcx>,
+    findings: Findings,
+}
+
+/// What was found before a level started, which each of its groups starts from.
+struct LevelStart<'a, 'tcx> {
+    contexts: &'a Contexts<'tcx>,
+    globals: &'a Globals,
+    merged: &'a Merged<'tcx>,
+}
+
+/// Summarize every function reachable from `roots` without tainted arguments, and report the sinks they reach.
+///
+/// Functions which other crates can call are `exported`,
+/// in which case the roots are also summarized with each of their arguments tainted.
+pub(crate) fn summarize_all<'tcx>(
+    tcx: TyCtxt<'tcx>,
+    env: &TaintEnv,
+    roots: &[Instance<'tcx>],
+    exported: bool,
+) -> (Contexts<'tcx>, Findings) {
+    let graph = CallGraph::new(tcx, env, roots);
+    let levels = graph.levels();
+
+    // A function may read a static or a channel before the function which taints it has been analyzed,
+    // and a call may share its summary with one analyzed before it whose arguments were less tainted,
+    // so we repeat the analysis until neither the tainted global memory nor the merged arguments change.
+    let mut globals = Globals::new();
+    let mut merged = Merged::new();
+    loop {
+        let before = (globals.clone(), merged.clone());
+        let mut contexts = Contexts::new();
+        let mut findings = Findings::new();
+
+        for level in &levels {
+            let start = LevelStart {
+                contexts: &contexts,
+                globals: &globals,
+                merged: &merged,
+            };
+            let outcomes: Vec<Outcome<'tcx>> = par_map(level, |group: &Vec<usize>| {
+                summarize_group(tcx, env, &graph, group, exported, &start)
+            });
+
+            for outcome in outcomes {
+                for (key, summary) in outcome.contexts {
+                    contexts.entry(key).or_insert(summary);
+                }
+                globals.extend(outcome.globals);
+                for (context, args) in outcome.merged {
+                    match merged.entry(context) {
+                        Entry::Occupied(mut entry) => {
+                            join_args(entry.get_mut(), (&args.0, &args.1))
+                        }
+                        Entry::Vacant(entry) => {
+                            entry.insert(args);
+                        }
+                    }
+                }
+                for finding in outcome.findings {
+                    if !findings.contains(&finding) {
+                        findings.push(finding);
+                    }
+                }
+            }
+        }
+
+        if (&globals, &merged) == (&before.0, &before.1) {
+            return (contexts, findings);
+        }
+    }
+}
+
+fn summarize_group<'tcx>(
+    tcx: TyCtxt<'tcx>,
+    env: &TaintEnv,
+    graph: &CallGraph<'tcx>,
+    group: &[usize],
+    exported: bool,
+    start: &LevelStart<'_, 'tcx>,
+) -> Outcome<'tcx> {
+    let contexts = Rc::new(ContextStore::new(start.contexts, start.merged.clone()));
+    let globals = Rc::new(RefCell::new(start.globals.clone()));
+    let findings = Rc::new(RefCell::new(Findings::new()));
+
+    // Sinks reached without any tainted arguments are reported, as they would be from `main`.
+    // Every function in the group has been analyzed that way before we taint arguments,
+    // so the sinks reached only with tainted arguments are left for the callers to report.
+    for &node in group {
+        summarize(tcx, env, graph.nodes[node], None, &contexts, &globals, &findings);
+    }
+    if exported {
+        let speculative = Rc::new(RefCell::new(Findings::new()));
+        for &node in group.iter().filter(|&&node| node < graph.roots) {
+            let instance = graph.nodes[node];
+            for arg in 0..tcx.instance_mir(instance.def).arg_count {
+                summarize(
+                    tcx,
+                    env,
+                    instance,
+                    Some(arg),
+                    &contexts,
+                    &globals,
+                    &speculative,
+                );
+            }
+        }
+    }
+
+    Outcome {
+        contexts: contexts.take_local(),
+        globals: globals.take(),
+        merged: contexts.take_merged(),
+        findings: findings.take(),
+    }
+}
+
+/// Summarize a function with either no tainted arguments or only the `tainted` one.
+fn summarize<'tcx, 'inter>(
+    tcx: TyCtxt<'tcx>,
+    env: &'inter TaintEnv,
+    instance: Instance<'tcx>,
+    tainted: Option<usize>,
+    contexts: &Rc<ContextStore<'tcx, 'inter>>,
+    globals: &Rc<RefCell<Globals>>,
+    findings: &Rc<RefCell<Findings>>,
+) {
+    let arg_count = tcx.instance_mir(instance.def).arg_count;
+    let init = (0..arg_count).map(|i| Some(Some(i) == tainted)).collect();
+    let init_refs = vec![Default::default(); arg_count];
+
+    TaintAnalysis::summarize(
+        tcx,
+        env,
+        (instance, init, init_refs, Vec::new(), None),
+        contexts.clone(),
+        globals.clone(),
+        findings.clone(),
+    );
+}
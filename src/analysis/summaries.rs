@@ -0,0 +1,477 @@
+//! Summaries of the functions in a library, written next to its rlib so that the crates which depend on it
+//! can use them instead of treating its functions as opaque.
+//!
+//! The file is plain text. Its first line holds the hash of the build of the library it was written for,
+//! since a library which is rebuilt without the analysis leaves the old file behind.
+//! It is followed by one tab-separated entry per line:
+//!
+//! ```text
+//! source     <def path hash>
+//! sink       <def path hash> [<label>]
+//! sanitizer  <def path hash>
+//! none       <def path hash>
+//! tainted_type <def path hash> [<kind>]
+//! sink_type  <def path hash> [<label>]
+//! summary    <def path hash> <init> <normal> <unwind> <tainted panic> <partial> <effect>...
+//! ```
+//!
+//! The taint of the arguments on entry (`<init>`) and of the return place and arguments on exit
+//! (`<normal>` and `<unwind>`) are written as strings of `0` and `1`, with `-` for an exit that is never taken.
+//! Each effect is one of:
+//!
+//! ```text
+//! sink:<name>[#<label>]   a sink the function may pass tainted input to
+//! global:<global>         global memory it may taint
+//! return:<global>         global memory its return value may refer to
+//! ```
+//!
+//! Global memory is written as `static:<def path hash>` or `alloc:<def path hash>:<block>:<statement>`.
+//! The findings and dependencies of a summary only matter to the crate it was computed in, so they are left out.
+
+use std::{
+    collections::{BTreeSet, HashMap},
+    fs,
+    hash::Hash,
+    path::PathBuf,
+    str::Lines,
+};
+
+use rustc_data_structures::{fingerprint::Fingerprint, stable_hasher::StableHasher};
+use rustc_hir::def::DefKind;
+use rustc_hir::def_id::{CrateNum, DefId, DefIndex, DefPathHash, LOCAL_CRATE};
+use rustc_index::bit_set::BitSet;
+use rustc_metadata::creader::CStore;
+use rustc_middle::{
+    mir::{BasicBlock, Local, Location},
+    ty::{InstanceDef, TyCtxt},
+};
+use rustc_session::{
+    config::{CrateType, OutFileName},
+    output::filename_for_input,
+};
+
+use crate::eval::attributes::AttrInfo;
+
+use super::{
+    taint_analysis::{Contexts, Effects, GlobalLoc, InitSet, Sink, Summary},
+    taint_domain::TaintDomain,
+};
+
+const HEADER: &str = "taint-summaries 3";
+
+/// The summaries of the functions in our dependencies, by the taint of their arguments.
+#[derive(Clone, Debug, Default)]
+pub struct ExternSummaries {
+    summaries: HashMap<DefId, HashMap<Vec<bool>, Summary>>,
+    /// The contents of every file which was loaded.
+    files: Vec<String>,
+}
+
+impl ExternSummaries {
+    /// Load the summaries of every dependency which was analyzed, and add the functions they mark to `info`.
+    pub fn load(tcx: TyCtxt<'_>, info: &mut AttrInfo) -> Self {
+        let mut summaries = ExternSummaries::default();
+        let mut defs = ExternDefs::default();
+
+        for &cnum in tcx.crates(()) {
+            for path in tcx.crate_extern_paths(cnum) {
+                let path = path.with_extension("taint");
+                let Ok(contents) = fs::read_to_string(&path) else {
+                    continue;
+                };
+
+                // An rlib which was rebuilt without the analysis may have left an outdated file behind,
+                // whose functions may no longer exist.
+                let mut lines = contents.lines();
+                match lines.next() {
+                    Some(header) if header == format!("{}\t{}", HEADER, tcx.crate_hash(cnum)) => {
+                        if summaries.parse(tcx, lines, info, &mut defs).is_none() {
+                            tcx.sess.warn(format!(
+                                "ignoring malformed taint summaries in `{}`",
+                                path.display()
+                            ));
+                        }
+                    }
+                    Some(header) if header.starts_with("taint-summaries") => {
+                        tcx.sess.warn(format!(
+                            "ignoring outdated taint summaries in `{}`",
+                            path.display()
+                        ));
+                        tcx.sess.note_without_error(format!(
+                            "they were written for another build of `{}`, which was not analyzed",
+                            tcx.crate_name(cnum)
+                        ));
+                    }
+                    _ => tcx.sess.warn(format!(
+                        "ignoring malformed taint summaries in `{}`",
+                        path.display()
+                    )),
+                }
+                summaries.files.push(contents);
+                break;
+            }
+        }
+
+        summaries
+    }
+
+    /// A fingerprint of everything which was loaded, to tell when it changes.
+    pub(crate) fn fingerprint(&self) -> Fingerprint {
+        let mut hasher = StableHasher::new();
+        self.files.hash(&mut hasher);
+        hasher.finish()
+    }
+
+    /// Parse the entries after the header, looking up the definitions they refer to in `defs`.
+    fn parse(
+        &mut self,
+        tcx: TyCtxt<'_>,
+        lines: Lines<'_>,
+        info: &mut AttrInfo,
+        defs: &mut ExternDefs,
+    ) -> Option<()> {
+        for line in lines {
+            let mut fields = line.split('\t');
+            let kind = fields.next()?;
+            let id = defs.get(tcx, parse_hash(fields.next()?)?)?;
+
+            match kind {
+                "source" => info.sources.push(id),
+                "sink" => {
+                    info.sinks.push(id);
+                    if let Some(label) = fields.next() {
+                        info.labels.insert(id, label.to_owned());
+                    }
+                }
+                "sanitizer" => info.sanitizers.push(id),
+                "none" => info.none.push(id),
+                "sink_type" => {
+                    info.sink_types.push(id);
+                    if let Some(label) = fields.next() {
+                        info.labels.insert(id, label.to_owned());
+                    }
+                }
+                "tainted_type" => {
+                    info.tainted_types.push(id);
+                    if let Some(kind) = fields.next() {
+                        info.kinds.insert(id, kind.to_owned());
+                    }
+                }
+                "summary" => {
+                    let init = parse_bits(fields.next()?)?;
+                    let normal = parse_exit(fields.next()?)?;
+                    let unwind = parse_exit(fields.next()?)?;
+                    let tainted_panic = parse_bits(fields.next()?)?.first().copied()?;
+                    let partial = parse_bits(fields.next()?)?.first().copied()?;
+                    let mut summary = Summary {
+                        normal,
+                        unwind,
+                        tainted_panic,
+                        return_refs: BTreeSet::new(),
+                        effects: Effects {
+                            partial,
+                            ..Default::default()
+                        },
+                    };
+                    for field in fields {
+                        match field.split_once(':')? {
+                            ("sink", sink) => {
+                                summary.effects.sinks.insert(parse_sink(sink));
+                            }
+                            ("global", loc) => {
+                                let loc = parse_loc(loc, |hash| defs.get(tcx, hash))?;
+                                summary.effects.globals.insert(loc);
+                            }
+                            ("return", loc) => {
+                                let loc = parse_loc(loc, |hash| defs.get(tcx, hash))?;
+                                summary.return_refs.insert(loc);
+                            }
+                            _ => return None,
+                        }
+                    }
+                    self.summaries.entry(id).or_default().insert(init, summary);
+                }
+                _ => return None,
+            }
+        }
+
+        Some(())
+    }
+
+    /// Get the summary of a function from another crate, given the taint of its arguments.
+    ///
+    /// Only some combinations are summarized, so if there is no exact match,
+    /// we join the summaries in which each tainted argument is tainted on its own.
+    pub(crate) fn get(&self, id: DefId, init: &InitSet) -> Option<Summary> {
+        let summaries = self.summaries.get(&id)?;
+        let init = init.iter().map(|t| t.unwrap_or(false)).collect::<Vec<_>>();
+
+        if let Some(summary) = summaries.get(&init) {
+            return Some(summary.clone());
+        }
+
+        let mut summary = summaries.get(&vec![false; init.len()])?.clone();
+        for (i, _) in init.iter().enumerate().filter(|(_, &t)| t) {
+            let mut single = vec![false; init.len()];
+            single[i] = true;
+            summary.join(summaries.get(&single)?);
+        }
+        Some(summary)
+    }
+
+    /// Write the summaries of the functions in the local crate which other crates can call,
+    /// along with the functions it marks, next to each library the crate is compiled to.
+    pub(crate) fn store(tcx: TyCtxt<'_>, info: &AttrInfo, contexts: &Contexts<'_>) {
+        let mut lines = vec![format!("{}\t{}", HEADER, tcx.crate_hash(LOCAL_CRATE))];
+
+        let markers = [
+            ("source", &info.sources),
+            ("sink", &info.sinks),
+            ("sanitizer", &info.sanitizers),
+            ("none", &info.none),
+            ("tainted_type", &info.tainted_types),
+            ("sink_type", &info.sink_types),
+        ];
+        for (kind, ids) in markers {
+            for id in ids.iter().filter(|id| id.is_local()) {
+                let mut line = format!("{}\t{}", kind, format_hash(tcx.def_path_hash(*id)));
+                let name = match kind {
+                    "sink" | "sink_type" => info.labels.get(id),
+                    "tainted_type" => info.kinds.get(id),
+                    _ => None,
+                };
+                if let Some(name) = name {
+                    line.push_str(&format!("\t{}", name));
+                }
+                lines.push(line);
+            }
+        }
+
+        let mut summaries = Vec::new();
+        for ((instance, init, init_refs, calls, receiver), summary) in contexts {
+            let InstanceDef::Item(id) = instance.def else {
+                continue;
+            };
+            // Summaries which depend on generic arguments, global memory, the calls leading to them
+            // or the object they were called on cannot be used elsewhere.
+            if !id.is_local()
+                || !instance.args.is_empty()
+                || !matches!(tcx.def_kind(id), DefKind::Fn | DefKind::AssocFn)
+                || init_refs.iter().any(|refs| !refs.is_empty())
+                || !calls.is_empty()
+                || receiver.is_some()
+            {
+                continue;
+            }
+
+            let init = init.iter().map(|t| t.unwrap_or(false)).collect::<Vec<_>>();
+            let mut fields = vec![
+                "summary".to_owned(),
+                format_hash(tcx.def_path_hash(id)),
+                format_bits(init.iter().copied()),
+                format_exit(&summary.normal, init.len()),
+                format_exit(&summary.unwind, init.len()),
+                format_bits([summary.tainted_panic]),
+                format_bits([summary.effects.partial]),
+            ];
+            let effects = &summary.effects;
+            fields.extend(
+                effects
+                    .sinks
+                    .iter()
+                    .map(|sink| format!("sink:{}", format_sink(sink))),
+            );
+            fields.extend(
+                effects
+                    .globals
+                    .iter()
+                    .map(|&loc| format!("global:{}", format_loc(tcx, loc))),
+            );
+            fields.extend(
+                summary
+                    .return_refs
+                    .iter()
+                    .map(|&loc| format!("return:{}", format_loc(tcx, loc))),
+            );
+            summaries.push(fields.join("\t"));
+        }
+        // Keep the file stable between runs, since the cache is a `HashMap`.
+        summaries.sort();
+        lines.extend(summaries);
+
+        let contents = lines.join("\n") + "\n";
+        for path in summary_paths(tcx) {
+            if let Err(e) = fs::write(&path, &contents) {
+                tcx.sess.warn(format!(
+                    "could not write taint summaries to `{}`: {}",
+                    path.display(),
+                    e
+                ));
+            }
+        }
+    }
+}
+
+/// The summaries are written next to every library output, with the `taint` extension.
+fn summary_paths(tcx: TyCtxt<'_>) -> Vec<PathBuf> {
+    let outputs = tcx.output_filenames(());
+    let crate_name = tcx.crate_name(LOCAL_CRATE);
+
+    tcx.crate_types()
+        .iter()
+        .filter(|&&crate_type| crate_type != CrateType::Executable)
+        .filter_map(|&crate_type| {
+            match filename_for_input(tcx.sess, crate_type, crate_name, outputs) {
+                OutFileName::Real(path) => Some(path.with_extension("taint")),
+                OutFileName::Stdout => None,
+            }
+        })
+        .collect()
+}
+
+/// The definitions of a dependency by their hash.
+///
+/// Hashes read from a file are looked up here rather than with `TyCtxt::def_path_hash_to_def_id`,
+/// which panics on a hash the crate does not define.
+fn extern_defs(tcx: TyCtxt<'_>, cnum: CrateNum) -> HashMap<DefPathHash, DefId> {
+    let count = CStore::from_tcx(tcx).num_def_ids_untracked(cnum);
+    (0..count)
+        .map(|index| {
+            let id = DefId {
+                krate: cnum,
+                index: DefIndex::from_usize(index),
+            };
+            (tcx.def_path_hash(id), id)
+        })
+        .collect()
+}
+
+/// The definitions of the dependencies by their hash, for those dependencies which hashes were looked up in.
+#[derive(Debug, Default)]
+pub(super) struct ExternDefs(HashMap<CrateNum, HashMap<DefPathHash, DefId>>);
+
+impl ExternDefs {
+    pub(super) fn get(&mut self, tcx: TyCtxt<'_>, hash: DefPathHash) -> Option<DefId> {
+        let &cnum = tcx
+            .crates(())
+            .iter()
+            .find(|&&cnum| tcx.stable_crate_id(cnum) == hash.stable_crate_id())?;
+        self.0
+            .entry(cnum)
+            .or_insert_with(|| extern_defs(tcx, cnum))
+            .get(&hash)
+            .copied()
+    }
+}
+
+pub(super) fn format_loc(tcx: TyCtxt<'_>, loc: GlobalLoc) -> String {
+    match loc {
+        GlobalLoc::Static(id) => format!("static:{}", format_hash(tcx.def_path_hash(id))),
+        GlobalLoc::Alloc(id, location) => format!(
+            "alloc:{}:{}:{}",
+            format_hash(tcx.def_path_hash(id)),
+            location.block.as_u32(),
+            location.statement_index
+        ),
+    }
+}
+
+/// Parse global memory written by `format_loc`, given how to look up the definition of a hash.
+pub(super) fn parse_loc(
+    s: &str,
+    def: impl FnOnce(DefPathHash) -> Option<DefId>,
+) -> Option<GlobalLoc> {
+    let mut parts = s.split(':');
+    let kind = parts.next()?;
+    let id = def(parse_hash(parts.next()?)?)?;
+
+    match kind {
+        "static" => Some(GlobalLoc::Static(id)),
+        "alloc" => {
+            let block = BasicBlock::from_u32(parts.next()?.parse().ok()?);
+            let statement_index = parts.next()?.parse().ok()?;
+            Some(GlobalLoc::Alloc(
+                id,
+                Location {
+                    block,
+                    statement_index,
+                },
+            ))
+        }
+        _ => None,
+    }
+}
+
+pub(super) fn format_fingerprint(fingerprint: Fingerprint) -> String {
+    let mut s = String::with_capacity(32);
+    for b in fingerprint.to_le_bytes() {
+        s.push_str(&format!("{:02x}", b));
+    }
+    s
+}
+
+pub(super) fn format_hash(hash: DefPathHash) -> String {
+    format_fingerprint(hash.0)
+}
+
+pub(super) fn parse_hash(s: &str) -> Option<DefPathHash> {
+    if s.len() != 32 {
+        return None;
+    }
+    let mut bytes = [0; 16];
+    for (i, b) in bytes.iter_mut().enumerate() {
+        *b = u8::from_str_radix(s.get(2 * i..2 * i + 2)?, 16).ok()?;
+    }
+    Some(DefPathHash(Fingerprint::from_le_bytes(bytes)))
+}
+
+fn format_sink((name, label): &Sink) -> String {
+    match label {
+        Some(label) => format!("{}#{}", name, label),
+        None => name.clone(),
+    }
+}
+
+fn parse_sink(s: &str) -> Sink {
+    match s.split_once('#') {
+        Some((name, label)) => (name.to_owned(), Some(label.to_owned())),
+        None => (s.to_owned(), None),
+    }
+}
+
+fn format_bits(bits: impl IntoIterator<Item = bool>) -> String {
+    bits.into_iter()
+        .map(|b| if b { '1' } else { '0' })
+        .collect()
+}
+
+fn parse_bits(s: &str) -> Option<Vec<bool>> {
+    s.chars()
+        .map(|c| match c {
+            '0' => Some(false),
+            '1' => Some(true),
+            _ => None,
+        })
+        .collect()
+}
+
+/// Only the return place and the arguments of an exit are visible to the caller.
+pub(super) fn format_exit(exit: &Option<BitSet<Local>>, arg_count: usize) -> String {
+    match exit {
+        Some(state) => format_bits((0..=arg_count).map(|i| state.get_taint(Local::from_usize(i)))),
+        None => "-".to_owned(),
+    }
+}
+
+pub(super) fn parse_exit(s: &str) -> Option<Option<BitSet<Local>>> {
+    if s == "-" {
+        return Some(None);
+    }
+
+    let bits = parse_bits(s)?;
+    let mut state = BitSet::new_empty(bits.len());
+    for (i, _) in bits.iter().enumerate().filter(|(_, &t)| t) {
+        state.insert(Local::from_usize(i));
+    }
+    Some(Some(state))
+}
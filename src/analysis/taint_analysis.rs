@@ -1,34 +1,502 @@
 use std::{
-    cell::RefCell,
-    collections::{HashMap, HashSet},
+    cell::{Cell, Ref, RefCell},
+    collections::{BTreeSet, HashMap, HashSet},
     rc::Rc,
 };
 
-use rustc_errors::struct_span_err;
+use rustc_data_structures::fx::FxIndexMap;
+use rustc_errors::error_code;
+use rustc_hir::def::DefKind;
 use rustc_hir::def_id::DefId;
+use rustc_hir::CRATE_HIR_ID;
 use rustc_index::bit_set::BitSet;
 use rustc_middle::{
     mir::{
-        traversal::reverse_postorder, visit::Visitor, BasicBlock, Body, Constant, HasLocalDecls,
-        Local, Location, Operand, Place, Rvalue, Statement, StatementKind, Terminator,
-        TerminatorKind,
+        visit::Visitor, AggregateKind, BasicBlock, Body, CastKind, Constant, HasLocalDecls, Local,
+        Location, Operand, Place, Rvalue, Statement, StatementKind, Terminator, TerminatorKind,
+        UnwindAction,
     },
-    ty::{TyCtxt, TyKind},
+    ty::{self, Instance, ParamEnv, TyCtxt, TyKind},
 };
 
-use rustc_mir_dataflow::{Analysis, AnalysisDomain, Forward, CallReturnPlaces};
-use rustc_span::Span;
+use rustc_mir_dataflow::{Analysis, AnalysisDomain, CallReturnPlaces, Forward};
+use rustc_span::{sym, Span};
 
 use tracing::instrument;
 
-use crate::eval::attributes::{AttrInfo, AttrInfoKind};
+use crate::eval::{
+    attributes::{find_suppressions, AttrInfo, AttrInfoKind, TaintAttributeFinder},
+    config::{ContextSensitivity, TaintConfig},
+    lints,
+};
 
-use super::taint_domain::{PointsAwareTaintDomain, TaintDomain};
+use super::{
+    assertions::Assertions,
+    budget::{Budget, Exceeded},
+    declassify::Declassifications,
+    dump::MirDump,
+    export::CallGraphExport,
+    incremental::{body_deps, IncrementalCache},
+    models::{thread_local_key, Model},
+    summaries::ExternSummaries,
+    taint_domain::{PointsAwareTaintDomain, TaintDomain},
+};
 
 pub(crate) type PointsMap = HashMap<Local, HashSet<Local>>;
-pub(crate) type Contexts = HashMap<(DefId, InitSet), Option<BitSet<Local>>>;
+pub(crate) type Contexts<'tcx> = HashMap<ContextKey<'tcx>, Summary>;
+pub(crate) type ContextKey<'tcx> = (
+    Instance<'tcx>,
+    InitSet,
+    InitRefs,
+    CallString<'tcx>,
+    Receiver<'tcx>,
+);
+
+/// The taint of the arguments which are merged between calls, by the context they are merged in.
+pub(crate) type Merged<'tcx> = HashMap<ContextKey<'tcx>, (InitSet, InitRefs)>;
+
+/// The most recent calls leading to a function, by the function they were made from and where,
+/// when calls are told apart by where they were made from.
+pub(crate) type CallString<'tcx> = Vec<(Instance<'tcx>, Location)>;
+
+/// The object a method is called on, when calls to methods are told apart by their receivers:
+/// the function whose locals hold it, and the locals its receiver may refer to.
+pub(crate) type Receiver<'tcx> = Option<(Instance<'tcx>, BTreeSet<Local>)>;
+
+/// After this many iterations, the approximation of a recursive function is assumed to taint everything,
+/// so that functions with many paths through their recursion do not need an iteration for each.
+const WIDENING_BOUND: usize = 8;
+
+/// A function which is being analyzed, and may be called recursively.
+struct Frame<'tcx> {
+    key: ContextKey<'tcx>,
+    /// The summary recursive calls are given, which is refined until it includes the summary of the body.
+    approximation: Summary,
+    /// Whether a recursive call has used the approximation since it last changed.
+    read: bool,
+    /// The lowest frame whose approximation the summary may depend on, possibly this one.
+    lowest: usize,
+    /// The number of provisional summaries when the current iteration started.
+    provisional: usize,
+}
+
+/// The summaries an analysis can use: those computed before it started, which may be shared between threads,
+/// and those computed since, which are not.
+pub(crate) struct ContextStore<'tcx, 'inter> {
+    shared: &'inter Contexts<'tcx>,
+    local: RefCell<Contexts<'tcx>>,
+    stack: RefCell<Vec<Frame<'tcx>>>,
+    /// The summaries which were computed from the approximation of a frame that is still on the stack,
+    /// by the lowest such frame. They are discarded if the approximation changes.
+    provisional: RefCell<FxIndexMap<ContextKey<'tcx>, usize>>,
+    /// The taint of the arguments which are merged between calls, including those of earlier iterations of the analysis.
+    merged: RefCell<Merged<'tcx>>,
+}
+
+impl<'tcx, 'inter> ContextStore<'tcx, 'inter> {
+    pub(crate) fn new(shared: &'inter Contexts<'tcx>, merged: Merged<'tcx>) -> Self {
+        ContextStore {
+            shared,
+            local: RefCell::new(Contexts::new()),
+            stack: RefCell::new(Vec::new()),
+            provisional: RefCell::new(FxIndexMap::default()),
+            merged: RefCell::new(merged),
+        }
+    }
+
+    /// Merge the taint of the arguments which the context sensitivity does not tell apart with that of other calls,
+    /// so that the calls share a summary which covers all of them.
+    ///
+    /// Calls which come later may still add to it, in which case the analysis is repeated,
+    /// starting with everything merged before, until nothing more is added.
+    fn merge(&self, key: &mut ContextKey<'tcx>, distinguished: impl Fn(usize) -> bool) {
+        let mut context = key.clone();
+        for i in (0..key.1.len()).filter(|&i| !distinguished(i)) {
+            context.1[i] = Some(false);
+            context.2[i].clear();
+        }
+
+        let mut merged = self.merged.borrow_mut();
+        let args = merged
+            .entry(context)
+            .or_insert_with(|| (key.1.clone(), key.2.clone()));
+        join_args(args, (&key.1, &key.2));
+        key.1 = args.0.clone();
+        key.2 = args.1.clone();
+    }
+
+    fn get(&self, key: &ContextKey<'tcx>) -> Option<Summary> {
+        let summary = match self.local.borrow().get(key) {
+            Some(summary) => summary.clone(),
+            None => self.shared.get(key)?.clone(),
+        };
+        if let Some(&lowest) = self.provisional.borrow().get(key) {
+            self.depend_on(lowest);
+        }
+        Some(summary)
+    }
+
+    /// The approximation of a function on the stack, if this is a recursive call.
+    fn approximation(&self, key: &ContextKey<'tcx>) -> Option<Summary> {
+        let (index, summary) = {
+            let mut stack = self.stack.borrow_mut();
+            let index = stack.iter().position(|frame| &frame.key == key)?;
+            stack[index].read = true;
+            (index, stack[index].approximation.clone())
+        };
+        self.depend_on(index);
+        Some(summary)
+    }
+
+    /// Record that the summary of the function being analyzed depends on the approximation in a frame.
+    fn depend_on(&self, index: usize) {
+        if let Some(top) = self.stack.borrow_mut().last_mut() {
+            top.lowest = top.lowest.min(index);
+        }
+    }
+
+    /// How many functions are being analyzed, each within the analysis of the one before.
+    fn depth(&self) -> usize {
+        self.stack.borrow().len()
+    }
+
+    fn push(&self, key: ContextKey<'tcx>) {
+        let mut stack = self.stack.borrow_mut();
+        let index = stack.len();
+        stack.push(Frame {
+            key,
+            approximation: Summary::default(),
+            read: false,
+            lowest: index,
+            provisional: self.provisional.borrow().len(),
+        });
+    }
+
+    /// Compare the summary of the body of the function on top of the stack with the approximation its recursive calls used.
+    /// If they used one which did not include it, the approximation is refined and `None` is returned,
+    /// so that the body is analyzed again.
+    fn refine(&self, summary: Summary, iteration: usize, locals: usize) -> Option<Summary> {
+        let mut stack = self.stack.borrow_mut();
+        let index = stack.len() - 1;
+        let frame = &mut stack[index];
+        if !frame.read {
+            return Some(summary);
+        }
+        if frame.approximation.includes(&summary) {
+            return Some(frame.approximation.clone());
+        }
+
+        frame.approximation.join(&summary);
+        if iteration >= WIDENING_BOUND {
+            frame.approximation.widen(locals);
+        }
+        frame.read = false;
+        frame.lowest = index;
+
+        // Anything computed from the old approximation is computed again.
+        let mut local = self.local.borrow_mut();
+        for (key, _) in self.provisional.borrow_mut().drain(frame.provisional..) {
+            local.remove(&key);
+        }
+        None
+    }
+
+    /// Record the summary of the function on top of the stack, and return whether it is final,
+    /// i.e. it does not depend on the approximation of a function which is still being analyzed.
+    fn pop(&self, summary: Summary) -> bool {
+        let frame = self.stack.borrow_mut().pop().unwrap();
+        let index = self.stack.borrow().len();
+
+        let is_final = frame.lowest >= index;
+        if is_final {
+            // What was computed from its approximation is final as well.
+            self.provisional.borrow_mut().truncate(frame.provisional);
+        } else {
+            self.provisional
+                .borrow_mut()
+                .insert(frame.key.clone(), frame.lowest);
+            self.depend_on(frame.lowest);
+        }
+        self.local.borrow_mut().insert(frame.key, summary);
+
+        is_final
+    }
+
+    /// The summaries computed since the store was created.
+    pub(crate) fn take_local(&self) -> Contexts<'tcx> {
+        self.local.take()
+    }
+
+    pub(crate) fn take_merged(&self) -> Merged<'tcx> {
+        self.merged.take()
+    }
+}
+
+/// Join the taint of the arguments of another call into those merged in a context.
+/// The arguments which the context tells apart are the same for every call in it.
+pub(crate) fn join_args(
+    (init, init_refs): &mut (InitSet, InitRefs),
+    (other, other_refs): (&InitSet, &InitRefs),
+) {
+    for (i, &tainted) in other.iter().enumerate() {
+        if init[i] != tainted {
+            init[i] = Some(init[i] == Some(true) || tainted == Some(true));
+        }
+        init_refs[i].extend(other_refs[i].iter().copied());
+    }
+}
+
+/// Memory which outlives the function that writes to it, and may be read by any other function.
+#[derive(Clone, Copy, Debug, PartialEq, Eq, PartialOrd, Ord, Hash)]
+pub enum GlobalLoc {
+    /// A `static` or thread-local.
+    Static(DefId),
+    /// Shared data such as a channel or an `Arc`, identified by the call which created it.
+    Alloc(DefId, Location),
+}
+
+/// Locals which refer to global memory, mapped to the locations they may refer to.
+pub(crate) type GlobalRefs = HashMap<Local, BTreeSet<GlobalLoc>>;
+
+/// The global memory which may hold a tainted value.
+///
+/// Since any function may write to it, this state is shared by every analyzed body.
+pub type Globals = HashSet<GlobalLoc>;
+
+/// The sinks which were reached by a possibly tainted value.
+pub type Findings = Vec<Finding>;
+
+/// A sink, by the name it was called with and its label, if it has one.
+pub(crate) type Sink = (String, Option<String>);
+
+pub(crate) type InitSet = Vec<Option<bool>>;
+pub(crate) type InitRefs = Vec<BTreeSet<GlobalLoc>>;
+
+/// Taint to apply to the caller's locals once the call in a block returns normally.
+pub(crate) type ReturnEffects = HashMap<BasicBlock, Vec<(Local, bool)>>;
+
+/// What a caller learns from analyzing a function.
+///
+/// The default summary is that of a function which never returns or unwinds.
+#[derive(Clone, Debug, Default)]
+pub(crate) struct Summary {
+    /// The taint of each local of the callee when it returns, if it can.
+    pub(super) normal: Option<BitSet<Local>>,
+    /// The taint of each local of the callee when it unwinds, if it can.
+    pub(super) unwind: Option<BitSet<Local>>,
+    /// Whether the callee may unwind with a tainted panic payload.
+    pub(super) tainted_panic: bool,
+    /// The global memory the return value may refer to.
+    pub(super) return_refs: BTreeSet<GlobalLoc>,
+    pub(super) effects: Effects,
+}
+
+/// What may happen during a call besides the taint at its exits, including in its callees.
+#[derive(Clone, Debug, Default)]
+pub(crate) struct Effects {
+    /// The sinks which may receive tainted input.
+    pub(super) sinks: BTreeSet<Sink>,
+    /// The calls to those sinks in the current crate.
+    pub(super) findings: Findings,
+    /// The global memory which may be tainted.
+    pub(super) globals: BTreeSet<GlobalLoc>,
+    /// The definitions in the current crate whose changes may change the summary, such as the bodies of callees.
+    pub(super) deps: BTreeSet<DefId>,
+    /// Whether the analysis of the function or one of its callees was cut short by the budget.
+    pub(super) partial: bool,
+}
+
+impl Effects {
+    pub(super) fn join(&mut self, other: &Effects) {
+        self.sinks.extend(other.sinks.iter().cloned());
+        for finding in &other.findings {
+            if !self.findings.contains(finding) {
+                self.findings.push(finding.clone());
+            }
+        }
+        self.globals.extend(other.globals.iter().copied());
+        self.deps.extend(other.deps.iter().copied());
+        self.partial |= other.partial;
+    }
+
+    fn includes(&self, other: &Effects) -> bool {
+        self.sinks.is_superset(&other.sinks)
+            && other.findings.iter().all(|f| self.findings.contains(f))
+            && self.globals.is_superset(&other.globals)
+            && self.deps.is_superset(&other.deps)
+            && (self.partial || !other.partial)
+    }
+}
+
+impl Summary {
+    /// Combine two summaries of the same function, as if either could have happened.
+    pub(super) fn join(&mut self, other: &Summary) {
+        fn join_exit(exit: &mut Option<BitSet<Local>>, other: &Option<BitSet<Local>>) {
+            match (exit.as_mut(), other) {
+                (Some(state), Some(other)) => {
+                    state.union(other);
+                }
+                (None, Some(other)) => *exit = Some(other.clone()),
+                (_, None) => {}
+            }
+        }
+
+        join_exit(&mut self.normal, &other.normal);
+        join_exit(&mut self.unwind, &other.unwind);
+        self.tainted_panic |= other.tainted_panic;
+        self.return_refs.extend(other.return_refs.iter().copied());
+        self.effects.join(&other.effects);
+    }
+
+    /// Whether joining `other` would not change the summary.
+    fn includes(&self, other: &Summary) -> bool {
+        fn includes_exit(exit: &Option<BitSet<Local>>, other: &Option<BitSet<Local>>) -> bool {
+            match (exit, other) {
+                (_, None) => true,
+                (None, Some(_)) => false,
+                (Some(state), Some(other)) => state.superset(other),
+            }
+        }
+
+        includes_exit(&self.normal, &other.normal)
+            && includes_exit(&self.unwind, &other.unwind)
+            && (self.tainted_panic || !other.tainted_panic)
+            && self.return_refs.is_superset(&other.return_refs)
+            && self.effects.includes(&other.effects)
+    }
+
+    /// Assume that every local of a function with `locals` locals may be tainted when it returns or unwinds,
+    /// and that it may unwind with a tainted payload.
+    fn widen(&mut self, locals: usize) {
+        self.normal = Some(BitSet::new_filled(locals));
+        self.unwind = Some(BitSet::new_filled(locals));
+        self.tainted_panic = true;
+    }
+
+    /// The summary of a function whose analysis was cut short, with the effects found until then.
+    fn conservative(effects: Effects, locals: usize) -> Self {
+        let mut summary = Summary {
+            effects: Effects {
+                partial: true,
+                ..effects
+            },
+            ..Summary::default()
+        };
+        summary.widen(locals);
+        summary
+    }
+}
+
+/// What the analysis knows about the crate before it starts.
+pub struct TaintEnv {
+    /// All the functions that have been marked, including those in our dependencies.
+    pub info: AttrInfo,
+    pub config: TaintConfig,
+    /// The summaries of the functions in our dependencies.
+    pub summaries: ExternSummaries,
+    /// The summaries from the previous run.
+    pub cache: IncrementalCache,
+    pub budget: Budget,
+    pub call_graph: CallGraphExport,
+    pub mir_dump: MirDump,
+    pub declassifications: Declassifications,
+    pub assertions: Assertions,
+}
 
-type InitSet = Vec<Option<bool>>;
+impl TaintEnv {
+    pub fn new(tcx: TyCtxt<'_>, config: &TaintConfig) -> Self {
+        // Find all functions in the current crate that have been tagged
+        let mut finder = TaintAttributeFinder::new(tcx);
+        tcx.hir().visit_all_item_likes_in_crate(&mut finder);
+
+        let mut info = finder.finish();
+        info.suppressions = find_suppressions(tcx);
+        let summaries = ExternSummaries::load(tcx, &mut info);
+        // An assertion is only checked if the function it is in is analyzed, rather than taken from the cache.
+        let assertions = Assertions::new(tcx);
+        let cache = if assertions.is_empty() {
+            IncrementalCache::load(tcx, &info, config, &summaries)
+        } else {
+            IncrementalCache::default()
+        };
+
+        TaintEnv {
+            info,
+            config: config.clone(),
+            summaries,
+            cache,
+            budget: Budget::new(config),
+            call_graph: CallGraphExport::new(config),
+            mir_dump: MirDump::new(config),
+            declassifications: Declassifications::new(config),
+            assertions,
+        }
+    }
+
+    /// The environment of an analysis in which `source` is the only source, a function or a tainted type,
+    /// and every other source function returns untainted values, to find the findings which come from it.
+    /// Only the findings of such an analysis are used, so it records nothing else, and does not use the cache.
+    pub fn only_source(&self, source: DefId) -> Self {
+        let mut info = self.info.clone();
+        let sources = std::mem::take(&mut info.sources);
+        info.sanitizers
+            .extend(sources.into_iter().filter(|&id| id != source));
+        if info.tainted_types.contains(&source) {
+            info.tainted_types = vec![source];
+        } else {
+            info.tainted_types.clear();
+            info.sources.push(source);
+        }
+
+        let config = TaintConfig {
+            call_graph: None,
+            dump_mir: None,
+            declassifications: None,
+            ..self.config.clone()
+        };
+        TaintEnv {
+            info,
+            summaries: self.summaries.clone(),
+            cache: IncrementalCache::default(),
+            budget: Budget::new(&config),
+            call_graph: CallGraphExport::new(&config),
+            mir_dump: MirDump::new(&config),
+            declassifications: Declassifications::new(&config),
+            assertions: Assertions::default(),
+            config,
+        }
+    }
+}
+
+/// A call to a sink which may receive tainted input.
+#[derive(Clone, Debug, PartialEq, Eq, Hash)]
+pub struct Finding {
+    pub(crate) name: String,
+    /// The label of the sink, which may decide how severe the finding is.
+    pub(crate) label: Option<String>,
+    pub(crate) span: Span,
+}
+
+impl Finding {
+    /// Report the finding at the level `taint::tainted_sink` has where it is, or the severity of its label.
+    pub fn emit(&self, tcx: TyCtxt<'_>, config: &TaintConfig) {
+        let hir_id = lints::enclosing_node(tcx, self.span).unwrap_or(CRATE_HIR_ID);
+        let severity = self
+            .label
+            .as_ref()
+            .and_then(|label| config.severities.get(label))
+            .copied();
+        lints::emit(
+            tcx,
+            lints::TAINTED_SINK,
+            hir_id,
+            self.span,
+            severity,
+            format!("function `{}` received tainted input", self.name),
+            |diag| diag.code(error_code!(T0001)),
+        );
+    }
+}
 
 /// A dataflow analysis that tracks whether a value may carry a taint.
 ///
@@ -37,61 +505,275 @@ type InitSet = Vec<Option<bool>>;
 pub struct TaintAnalysis<'tcx, 'inter> {
     /// We use the type context to emit errors and get the MIR for other functions.
     tcx: TyCtxt<'tcx>,
-    /// All the functions that have been marked
-    info: &'inter AttrInfo,
-    contexts: Rc<RefCell<Contexts>>,
+    env: &'inter TaintEnv,
+    /// The function being analyzed, which we need to resolve the generic callees in its body.
+    instance: Instance<'tcx>,
+    /// The calls which led to the function, if the context sensitivity tells calls apart by them.
+    calls: CallString<'tcx>,
+    /// The object the function was called on, if the context sensitivity tells calls apart by it.
+    receiver: Receiver<'tcx>,
+    contexts: Rc<ContextStore<'tcx, 'inter>>,
+    globals: Rc<RefCell<Globals>>,
+    findings: Rc<RefCell<Findings>>,
     init: InitSet,
     points: RefCell<PointsMap>,
+    refs: RefCell<GlobalRefs>,
+    returns: RefCell<ReturnEffects>,
+    tainted_panic: Cell<bool>,
+    effects: RefCell<Effects>,
+    /// The locals whose type is marked `#[taint::tainted_type]`, which are tainted whatever is assigned to them.
+    tainted_types: BitSet<Local>,
+    /// How many basic blocks the analysis has visited, while it is iterating to a fixpoint.
+    /// Once it reads its results, which visits the blocks again, it records nothing outside of its own state.
+    visited: Option<usize>,
+    exceeded: Option<Exceeded>,
 }
 
 impl<'tcx, 'inter> TaintAnalysis<'tcx, 'inter> {
-    /// Call on `main` function
-    pub fn new(tcx: TyCtxt<'tcx>, info: &'inter AttrInfo) -> Self {
-        Self::new_with_init(
-            tcx,
-            info,
-            Rc::new(RefCell::new(Contexts::new())),
-            InitSet::new(),
-        )
-    }
-
-    /// Call on dependencies
     #[inline]
     fn new_with_init(
         tcx: TyCtxt<'tcx>,
-        info: &'inter AttrInfo,
-        contexts: Rc<RefCell<Contexts>>,
-        init: InitSet,
+        env: &'inter TaintEnv,
+        (instance, init, init_refs, calls, receiver): ContextKey<'tcx>,
+        contexts: Rc<ContextStore<'tcx, 'inter>>,
+        globals: Rc<RefCell<Globals>>,
+        findings: Rc<RefCell<Findings>>,
     ) -> Self {
+        // Arguments that refer to global memory keep doing so in the callee.
+        let refs = init_refs
+            .into_iter()
+            .enumerate()
+            .filter(|(_, refs)| !refs.is_empty())
+            .map(|(i, refs)| (Local::from_usize(i + 1), refs))
+            .collect();
+
+        let body = tcx.instance_mir(instance.def);
+        let mut tainted_types = BitSet::new_empty(body.local_decls.len());
+        if !env.info.tainted_types.is_empty() {
+            for (local, decl) in body.local_decls.iter_enumerated() {
+                let ty = instance.subst_mir_and_normalize_erasing_regions(
+                    tcx,
+                    ParamEnv::reveal_all(),
+                    ty::EarlyBinder::bind(decl.ty),
+                );
+                if env.info.is_tainted_type(ty) {
+                    tainted_types.insert(local);
+                }
+            }
+        }
+
         TaintAnalysis {
             tcx,
-            info,
+            env,
+            instance,
+            calls,
+            receiver,
             contexts,
+            globals,
+            findings,
             init,
             points: RefCell::new(PointsMap::new()),
+            refs: RefCell::new(refs),
+            returns: RefCell::new(ReturnEffects::new()),
+            tainted_panic: Cell::new(false),
+            effects: RefCell::new(Effects::default()),
+            tainted_types,
+            visited: Some(0),
+            exceeded: None,
+        }
+    }
+}
+
+impl<'tcx, 'inter> TaintAnalysis<'tcx, 'inter> {
+    /// The aliases the analysis has found so far.
+    pub(crate) fn points(&self) -> Ref<'_, PointsMap> {
+        self.points.borrow()
+    }
+
+    /// Analyze a function given the taint of its arguments, or get the summary of an earlier analysis.
+    pub(crate) fn summarize(
+        tcx: TyCtxt<'tcx>,
+        env: &'inter TaintEnv,
+        key: ContextKey<'tcx>,
+        contexts: Rc<ContextStore<'tcx, 'inter>>,
+        globals: Rc<RefCell<Globals>>,
+        findings: Rc<RefCell<Findings>>,
+    ) -> Summary {
+        // A summary may have been computed by another analysis, which kept its effects to itself,
+        // so they are applied again.
+        if let Some(summary) = contexts.get(&key) {
+            Self::replay(&summary, &globals, &findings);
+            return summary;
+        }
+
+        // A recursive call gets an approximation of the summary, which starts out as that of a function which never returns.
+        // Its effects are applied once the approximation no longer changes.
+        if let Some(summary) = contexts.approximation(&key) {
+            return summary;
+        }
+
+        // The same goes for a summary from the previous run.
+        let cache_key = env.cache.key(tcx, &key, &globals.borrow());
+        if let Some(summary) = cache_key.as_ref().and_then(|k| env.cache.get(tcx, k)) {
+            Self::replay(&summary, &globals, &findings);
+            contexts.local.borrow_mut().insert(key, summary.clone());
+            return summary;
+        }
+
+        // Past the budget, the function is not analyzed at all.
+        // Its summary is not recorded, so that it can still be analyzed where it is called with less nesting.
+        let locals = tcx.instance_mir(key.0.def).local_decls.len();
+        if let Some(exceeded) = env.budget.check_depth(contexts.depth()) {
+            env.budget.record(key.0, exceeded);
+            return Summary::conservative(Effects::default(), locals);
+        }
+
+        // The body is analyzed until the approximation used by its recursive calls includes its summary.
+        // Since taint only grows with each iteration, this ends once the approximation is widened, if not before.
+        contexts.push(key.clone());
+        let summary = (1..)
+            .find_map(|iteration| {
+                let summary = Self::analyze(
+                    tcx,
+                    env,
+                    key.clone(),
+                    contexts.clone(),
+                    globals.clone(),
+                    findings.clone(),
+                );
+                contexts.refine(summary, iteration, locals)
+            })
+            .unwrap();
+
+        // Once the function summary has been computed, we insert it into the cache,
+        // unless it may still change with the approximation of a function it was called from.
+        if contexts.pop(summary.clone()) {
+            if let Some(cache_key) = cache_key {
+                env.cache.insert(tcx, cache_key, key.1.len(), &summary);
+            }
+        }
+
+        summary
+    }
+
+    fn replay(summary: &Summary, globals: &RefCell<Globals>, findings: &RefCell<Findings>) {
+        globals
+            .borrow_mut()
+            .extend(summary.effects.globals.iter().copied());
+        let mut findings = findings.borrow_mut();
+        for finding in &summary.effects.findings {
+            if !findings.contains(finding) {
+                findings.push(finding.clone());
+            }
+        }
+    }
+
+    #[instrument(skip(tcx, env, contexts, globals, findings))]
+    fn analyze(
+        tcx: TyCtxt<'tcx>,
+        env: &'inter TaintEnv,
+        key: ContextKey<'tcx>,
+        contexts: Rc<ContextStore<'tcx, 'inter>>,
+        globals: Rc<RefCell<Globals>>,
+        findings: Rc<RefCell<Findings>>,
+    ) -> Summary {
+        let target_body = tcx.instance_mir(key.0.def);
+        env.call_graph.function(key.0, &key.1);
+        let mut results =
+            TaintAnalysis::new_with_init(tcx, env, key.clone(), contexts, globals, findings)
+                .into_engine(tcx, target_body)
+                .pass_name("taint_analysis")
+                .iterate_to_fixpoint();
+        if let Some(exceeded) = results.analysis.exceeded {
+            env.budget.record(key.0, exceeded);
+            let effects = results.analysis.effects.take();
+            return Summary::conservative(effects, target_body.local_decls.len());
+        }
+        // Reading the results visits blocks again, which does not count against the budget.
+        results.analysis.visited = None;
+        if env.mir_dump.wants(tcx, key.0) {
+            // The aliases are found again as the dump visits the blocks, to show where each appears.
+            let points = results.analysis.points.take();
+            env.mir_dump
+                .write(tcx, (key.0, &key.1), target_body, &mut results);
+            results.analysis.points.replace(points);
+        }
+        let return_refs = results
+            .analysis
+            .refs
+            .borrow()
+            .get(&Local::from_usize(0))
+            .cloned()
+            .unwrap_or_default();
+        let tainted_panic = results.analysis.tainted_panic.get();
+        let mut effects = results.analysis.effects.take();
+        effects.deps.extend(body_deps(key.0));
+        let mut results = results.into_results_cursor(target_body);
+
+        // The summary is the join of the states at every point where the function returns.
+        // Generators in particular return once per suspension point.
+        // The states where it unwinds, either from a cleanup block or straight out of a call,
+        // are joined separately.
+        let mut normal: Option<BitSet<Local>> = None;
+        let mut unwind: Option<BitSet<Local>> = None;
+        for (block, data) in target_body.basic_blocks.iter_enumerated() {
+            let exit = match data.terminator().kind {
+                TerminatorKind::Return => &mut normal,
+                TerminatorKind::Resume => &mut unwind,
+                _ if data.terminator().unwind() == Some(&UnwindAction::Continue) => &mut unwind,
+                _ => continue,
+            };
+            results.seek_to_block_end(block);
+            match exit {
+                Some(state) => {
+                    state.union(results.get());
+                }
+                None => *exit = Some(results.get().clone()),
+            }
+        }
+
+        Summary {
+            normal,
+            unwind,
+            tainted_panic,
+            return_refs,
+            effects,
         }
     }
 }
 
 struct TransferFunction<'tcx, 'inter, 'intra> {
     tcx: TyCtxt<'tcx>,
-    info: &'inter AttrInfo,
-    contexts: Rc<RefCell<Contexts>>,
+    env: &'inter TaintEnv,
+    instance: Instance<'tcx>,
+    calls: &'intra CallString<'tcx>,
+    receiver: &'intra Receiver<'tcx>,
+    init: &'intra InitSet,
+    contexts: Rc<ContextStore<'tcx, 'inter>>,
+    globals: Rc<RefCell<Globals>>,
+    findings: Rc<RefCell<Findings>>,
     state: &'intra mut PointsAwareTaintDomain<'intra, Local>,
+    refs: &'intra mut GlobalRefs,
+    returns: &'intra mut ReturnEffects,
+    tainted_panic: &'intra Cell<bool>,
+    effects: &'intra mut Effects,
+    /// Whether the results are being read, so that the calls, sinks, declassifications and assertions
+    /// it reaches were already recorded.
+    reading: bool,
 }
 
-impl<'inter> AnalysisDomain<'inter> for TaintAnalysis<'_, '_> {
+impl<'tcx> AnalysisDomain<'tcx> for TaintAnalysis<'tcx, '_> {
     type Domain = BitSet<Local>;
     const NAME: &'static str = "TaintAnalysis";
 
     type Direction = Forward;
 
-    fn bottom_value(&self, body: &Body<'inter>) -> Self::Domain {
+    fn bottom_value(&self, body: &Body<'tcx>) -> Self::Domain {
         // bottom = definitely untainted
         BitSet::new_empty(body.local_decls().len())
     }
 
-    fn initialize_start_block(&self, body: &Body<'inter>, state: &mut Self::Domain) {
+    fn initialize_start_block(&self, body: &Body<'tcx>, state: &mut Self::Domain) {
         // For the main function, locals all start out untainted.
         // For other functions, however, we must check if they receive tainted parameters.
         if !self.init.is_empty() {
@@ -104,53 +786,106 @@ impl<'inter> AnalysisDomain<'inter> for TaintAnalysis<'_, '_> {
                 state.set_taint(arg, true);
             }
         }
+        state.union(&self.tainted_types);
     }
 }
 
-impl<'tcx, 'inter, 'intra> Analysis<'intra> for TaintAnalysis<'tcx, 'inter> {
+impl<'tcx> Analysis<'tcx> for TaintAnalysis<'tcx, '_> {
     fn apply_statement_effect(
         &mut self,
         state: &mut Self::Domain,
-        statement: &Statement<'intra>,
+        statement: &Statement<'tcx>,
         location: Location,
     ) {
+        if self.exceeded.is_some() {
+            state.insert_all();
+            return;
+        }
         TransferFunction {
             tcx: self.tcx,
-            info: self.info,
+            env: self.env,
+            instance: self.instance,
+            calls: &self.calls,
+            receiver: &self.receiver,
+            init: &self.init,
             contexts: self.contexts.clone(),
+            globals: self.globals.clone(),
+            findings: self.findings.clone(),
             state: &mut PointsAwareTaintDomain {
                 state,
                 map: &mut self.points.borrow_mut(),
             },
+            refs: &mut self.refs.borrow_mut(),
+            returns: &mut self.returns.borrow_mut(),
+            tainted_panic: &self.tainted_panic,
+            effects: &mut self.effects.borrow_mut(),
+            reading: self.visited.is_none(),
         }
         .visit_statement(statement, location);
+        state.union(&self.tainted_types);
     }
 
     fn apply_terminator_effect(
         &mut self,
         state: &mut Self::Domain,
-        terminator: &Terminator<'intra>,
+        terminator: &Terminator<'tcx>,
         location: Location,
     ) {
+        // Once the analysis is over budget, everything is assumed to be tainted,
+        // and the rest of the body is no longer looked at.
+        if let (Some(visited), None) = (self.visited.as_mut(), self.exceeded) {
+            *visited += 1;
+            self.exceeded = self.env.budget.check_blocks(*visited);
+        }
+        if self.exceeded.is_some() {
+            state.insert_all();
+            return;
+        }
         TransferFunction {
             tcx: self.tcx,
-            info: self.info,
+            env: self.env,
+            instance: self.instance,
+            calls: &self.calls,
+            receiver: &self.receiver,
+            init: &self.init,
             contexts: self.contexts.clone(),
+            globals: self.globals.clone(),
+            findings: self.findings.clone(),
             state: &mut PointsAwareTaintDomain {
                 state,
                 map: &mut self.points.borrow_mut(),
             },
+            refs: &mut self.refs.borrow_mut(),
+            returns: &mut self.returns.borrow_mut(),
+            tainted_panic: &self.tainted_panic,
+            effects: &mut self.effects.borrow_mut(),
+            reading: self.visited.is_none(),
         }
         .visit_terminator(terminator, location);
+        state.union(&self.tainted_types);
     }
 
     fn apply_call_return_effect(
         &mut self,
-        _state: &mut Self::Domain,
-        _block: BasicBlock,
-        _return_place: CallReturnPlaces<'_, 'intra>,
+        state: &mut Self::Domain,
+        block: BasicBlock,
+        _return_place: CallReturnPlaces<'_, 'tcx>,
     ) {
-        // do nothing
+        if self.exceeded.is_some() {
+            return;
+        }
+        // The effect of an analyzed callee differs between returning and unwinding,
+        // so the effect of returning is only applied on the edge to the return block.
+        if let Some(effects) = self.returns.borrow_mut().remove(&block) {
+            let mut state = PointsAwareTaintDomain {
+                state,
+                map: &mut self.points.borrow_mut(),
+            };
+            for (local, taint) in effects {
+                state.set_taint(local, taint);
+            }
+        }
+        state.union(&self.tainted_types);
     }
 }
 
@@ -160,8 +895,8 @@ impl std::fmt::Debug for TransferFunction<'_, '_, '_> {
     }
 }
 
-impl<'inter> Visitor<'inter> for TransferFunction<'_, '_, '_> {
-    fn visit_statement(&mut self, statement: &Statement<'inter>, _: Location) {
+impl<'tcx> Visitor<'tcx> for TransferFunction<'tcx, '_, '_> {
+    fn visit_statement(&mut self, statement: &Statement<'tcx>, _: Location) {
         let Statement { source_info, kind } = statement;
 
         self.visit_source_info(source_info);
@@ -171,7 +906,7 @@ impl<'inter> Visitor<'inter> for TransferFunction<'_, '_, '_> {
         }
     }
 
-    fn visit_terminator(&mut self, terminator: &Terminator<'inter>, _: Location) {
+    fn visit_terminator(&mut self, terminator: &Terminator<'tcx>, location: Location) {
         let Terminator { source_info, kind } = terminator;
 
         self.visit_source_info(source_info);
@@ -187,20 +922,30 @@ impl<'inter> Visitor<'inter> for TransferFunction<'_, '_, '_> {
                 fn_span,
                 ..
             } => {
-                self.t_visit_call(c, args, destination, fn_span);
+                self.t_visit_call(c, args, destination, fn_span, location);
             }
+            TerminatorKind::Drop { place, .. } => self.t_visit_drop(place, location),
             TerminatorKind::Assert { .. } => {}
+            // Unwinding leaves the function with the state of its cleanup blocks,
+            // which the summary records separately from the state on return.
+            TerminatorKind::Resume => {}
+            // Generators and `async fn` bodies are lowered to state machines before `optimized_mir`,
+            // so values held across a `yield` or `.await` are saved in fields of the generator,
+            // which we track through its first argument.
+            TerminatorKind::Yield { .. } => {}
+            TerminatorKind::GeneratorDrop => {}
             _ => {}
         }
     }
 }
 
-impl<'long> TransferFunction<'_, '_, '_>
-where
-    Self: Visitor<'long>,
-{
+impl<'tcx> TransferFunction<'tcx, '_, '_> {
     #[instrument]
-    fn t_visit_assign(&mut self, place: &Place, rvalue: &Rvalue) {
+    fn t_visit_assign(&mut self, place: &Place<'tcx>, rvalue: &Rvalue<'tcx>) {
+        // Assigning to a field or through a reference only overwrites part of the local,
+        // so any taint it already carries must be kept.
+        let partial_taint = !place.projection.is_empty() && self.state.get_taint(place.local);
+
         match rvalue {
             // If we assign a constant to a place, the place is clean.
             Rvalue::Use(Operand::Constant(_)) | Rvalue::UnaryOp(_, Operand::Constant(_)) => {
@@ -233,126 +978,567 @@ where
             }
             Rvalue::Ref(_region_kind, _borrow_kind, p) => {
                 self.state.add_ref(place, p);
+                self.state.propagate(p.local, place.local);
             }
 
             Rvalue::Repeat(_, _) => {}
             Rvalue::ThreadLocalRef(_) => {}
             Rvalue::AddressOf(_, _) => {}
             Rvalue::Len(_) => {}
-            Rvalue::Cast(_, _, _) => {}
+            Rvalue::Cast(_, Operand::Constant(_), _) => self.state.set_taint(place.local, false),
+            // Pointer casts, e.g. the unsizing of `&[T; N]` to `&[T]`, still point to the same place.
+            Rvalue::Cast(kind, Operand::Copy(p) | Operand::Move(p), _) => {
+                if let CastKind::PointerCoercion(_) | CastKind::PtrToPtr | CastKind::FnPtrToPtr =
+                    kind
+                {
+                    self.state.add_ref(place, p);
+                }
+                self.state.propagate(p.local, place.local);
+            }
             Rvalue::NullaryOp(_, _) => {}
             Rvalue::Discriminant(_) => {}
-            Rvalue::Aggregate(_, _) => {}
-            Rvalue::ShallowInitBox(_, _) | Rvalue::CopyForDeref(_) => {}
+            // Structs, tuples, closures and generators carry the taint of any of their fields.
+            // Closures and generators may also write through the references they capture,
+            // and other aggregates through the references and closures they hold.
+            Rvalue::Aggregate(box kind, operands) => {
+                let body = self.tcx.instance_mir(self.instance.def);
+                let captures = matches!(
+                    kind,
+                    AggregateKind::Closure(..) | AggregateKind::Generator(..)
+                );
+                for p in operands.iter().filter_map(|op| op.place()) {
+                    let ty = p.ty(body, self.tcx).ty;
+                    if captures
+                        || ty.is_ref()
+                        || ty.is_unsafe_ptr()
+                        || ty.is_closure()
+                        || ty.is_generator()
+                    {
+                        self.state.add_ref(place, &p);
+                    }
+                }
+                let tainted = operands.iter().any(|op| match op {
+                    Operand::Copy(p) | Operand::Move(p) => self.state.get_taint(p.local),
+                    Operand::Constant(_) => false,
+                });
+                self.state.set_taint(place.local, tainted);
+            }
+            // A copy of a reference that is about to be dereferenced, e.g. a generator's saved state.
+            Rvalue::CopyForDeref(p) => {
+                self.state.add_ref(place, p);
+                self.state.propagate(p.local, place.local);
+            }
+            Rvalue::ShallowInitBox(_, _) => {}
+        }
+
+        if partial_taint {
+            self.state.set_taint(place.local, true);
+        }
+
+        self.t_visit_global_assign(place, rvalue);
+    }
+
+    /// Global memory is tracked flow-insensitively, since any function may write to it.
+    /// A tainted value written through a reference to global memory taints it,
+    /// and reading through such a reference in any function yields a tainted value.
+    fn t_visit_global_assign(&mut self, place: &Place<'tcx>, rvalue: &Rvalue<'tcx>) {
+        match rvalue {
+            Rvalue::ThreadLocalRef(def_id) => {
+                self.t_add_refs(place.local, [GlobalLoc::Static(*def_id)]);
+            }
+            // A reference to the key of a `thread_local!` refers to its value as well.
+            Rvalue::Use(Operand::Constant(c)) => {
+                if let Some(def_id) = c
+                    .check_static_ptr(self.tcx)
+                    .or_else(|| thread_local_key(self.tcx, c))
+                {
+                    self.t_add_refs(place.local, [GlobalLoc::Static(def_id)]);
+                }
+            }
+            Rvalue::Use(Operand::Copy(p) | Operand::Move(p)) | Rvalue::CopyForDeref(p)
+                if p.is_indirect() =>
+            {
+                if self.t_global_taint(p.local) {
+                    self.state.set_taint(place.local, true);
+                }
+            }
+            Rvalue::Use(Operand::Copy(p) | Operand::Move(p)) | Rvalue::Ref(_, _, p) => {
+                let refs = self.t_global_refs(p.local);
+                self.t_add_refs(place.local, refs);
+            }
+            // Closures capturing one end of a channel can use it to communicate.
+            Rvalue::Aggregate(_, operands) => {
+                for p in operands.iter().filter_map(|op| op.place()) {
+                    let refs = self.t_global_refs(p.local);
+                    self.t_add_refs(place.local, refs);
+                }
+            }
+            _ => {}
+        }
+
+        if place.is_indirect() && self.state.get_taint(place.local) {
+            self.t_taint_globals(place.local);
         }
     }
 
+    fn t_global_refs(&self, local: Local) -> BTreeSet<GlobalLoc> {
+        self.refs.get(&local).cloned().unwrap_or_default()
+    }
+
+    fn t_add_refs(&mut self, local: Local, refs: impl IntoIterator<Item = GlobalLoc>) {
+        let mut refs = refs.into_iter().peekable();
+        if refs.peek().is_some() {
+            self.refs.entry(local).or_default().extend(refs);
+        }
+    }
+
+    /// Whether `local` refers to global memory which may hold a tainted value.
+    fn t_global_taint(&self, local: Local) -> bool {
+        let globals = self.globals.borrow();
+        self.t_global_refs(local)
+            .iter()
+            .any(|loc| globals.contains(loc))
+    }
+
+    fn t_taint_globals(&mut self, local: Local) {
+        let refs = self.t_global_refs(local);
+        self.effects.globals.extend(refs.iter().copied());
+        self.globals.borrow_mut().extend(refs);
+    }
+
     #[instrument]
     fn t_visit_call(
         &mut self,
-        func: &Constant,
-        args: &[Operand],
-        destination: &Place,
+        func: &Constant<'tcx>,
+        args: &[Operand<'tcx>],
+        destination: &Place<'tcx>,
         span: &Span,
+        location: Location,
     ) {
         let name = func.to_string();
-        let id = match func.literal.ty().kind() {
-            TyKind::FnDef(id, _args) => Some(id),
-            _ => None,
-        }
-        .unwrap();
+        let (id, callee) = self.t_resolve(func);
+        self.t_visit_sink_types(id, callee, &name, args, span);
 
-        match self.info.get_kind(id) {
+        match self.env.info.get_kind(&id) {
             Some(AttrInfoKind::Source) => self.t_visit_source_destination(destination),
             Some(AttrInfoKind::Sanitizer) => self.t_visit_sanitizer_destination(destination),
-            Some(AttrInfoKind::Sink) => self.t_visit_sink(name, args, span),
-            None => self.t_fn_call_analysis(args, id, destination),
+            Some(AttrInfoKind::Sink) => {
+                let label = self.env.info.labels.get(&id).cloned();
+                self.t_visit_sink((name, label), args, span)
+            }
+            None => match (callee, Model::of(self.tcx, id)) {
+                (_, Some(Model::Allocation)) => {
+                    self.t_visit_allocation(args, destination, location)
+                }
+                (_, Some(Model::Panic)) => self.t_visit_panic(name, args, span),
+                (_, Some(Model::Declassify)) => self.t_visit_declassify(args, destination, span),
+                (_, Some(Model::AssertTainted | Model::AssertClean)) => {
+                    self.t_visit_assertion(args, span)
+                }
+                (_, Some(Model::Expect)) => {
+                    self.t_visit_panic(name, &args[args.len() - 1..], span);
+                    self.t_opaque_call(args, destination);
+                }
+                (Some(instance), Some(Model::Spawn)) => {
+                    self.t_visit_spawn(instance, args, destination, location)
+                }
+                (Some(instance), Some(Model::LocalKey)) => {
+                    self.t_visit_local_key(instance, args, destination, location)
+                }
+                (Some(instance), _) if self.t_is_analyzable(instance) => {
+                    self.t_fn_call_analysis(args, instance, Some(destination), location, false)
+                }
+                (Some(instance), _) if !instance.def_id().is_local() => {
+                    self.t_extern_call(args, instance, destination, span, location)
+                }
+                _ => self.t_opaque_call(args, destination),
+            },
         }
     }
 
-    fn t_fn_call_analysis(
+    /// Values created by an allocation model are shared between all their clones,
+    /// so we track their contents as global memory identified by the call site.
+    fn t_visit_allocation(
+        &mut self,
+        args: &[Operand<'tcx>],
+        destination: &Place<'tcx>,
+        location: Location,
+    ) {
+        self.t_opaque_call(args, destination);
+
+        let loc = GlobalLoc::Alloc(self.instance.def_id(), location);
+        if self.state.get_taint(destination.local) {
+            self.globals.borrow_mut().insert(loc);
+        }
+        self.t_add_refs(destination.local, [loc]);
+    }
+
+    /// A panic payload may be reported to the user, or caught by `catch_unwind` and used like any other value.
+    fn t_visit_panic(&mut self, name: String, args: &[Operand], span: &Span) {
+        if args
+            .iter()
+            .filter_map(|arg| arg.place())
+            .any(|p| self.state.get_taint(p.local))
+        {
+            self.tainted_panic.set(true);
+            if self.env.config.panic_sinks {
+                self.t_visit_sink((name, None), args, span);
+            }
+        }
+    }
+
+    /// A declassified value is returned without its taint,
+    /// and whether it had any is recorded for `--taint-report-declassifications`.
+    fn t_visit_declassify(&mut self, args: &[Operand], destination: &Place, span: &Span) {
+        let tainted = args
+            .first()
+            .and_then(|arg| arg.place())
+            .map_or(false, |place| self.state.get_taint(place.local));
+        if !self.reading {
+            self.env.declassifications.record(*span, tainted);
+        }
+        self.t_visit_sanitizer_destination(destination);
+    }
+
+    /// An assertion is checked once the analysis is over, with the taint its value had in any context.
+    fn t_visit_assertion(&mut self, args: &[Operand], span: &Span) {
+        let tainted = args
+            .first()
+            .and_then(|arg| arg.place())
+            .map_or(false, |place| self.state.get_taint(place.local));
+        if !self.reading {
+            self.env.assertions.record(*span, tainted);
+        }
+    }
+
+    /// A spawned closure is analyzed at the point it is spawned, with the taint it captures.
+    /// Anything it shares with other threads goes through global memory,
+    /// so it does not matter that it may actually run later.
+    /// A panic in the closure does not unwind into the caller, but its payload is returned instead,
+    /// which is also how we model `catch_unwind`.
+    fn t_visit_spawn(
         &mut self,
-        args: &[Operand],
-        id: &rustc_hir::def_id::DefId,
-        destination: &Place,
+        spawn: Instance<'tcx>,
+        args: &[Operand<'tcx>],
+        destination: &Place<'tcx>,
+        location: Location,
     ) {
+        // The closure is the last argument of every function we model as spawning a thread.
+        match (closure_argument(spawn), args.last()) {
+            (Some(instance), Some(arg)) if self.t_is_analyzable(instance) => self
+                .t_fn_call_analysis(
+                    std::slice::from_ref(arg),
+                    instance,
+                    Some(destination),
+                    location,
+                    true,
+                ),
+            _ => self.t_opaque_call(args, destination),
+        }
+    }
+
+    /// The closure given to a `LocalKey` is called with a reference to the value of the thread local,
+    /// which the analysis treats as a static, so we pass it the key, which refers to it in the same way.
+    /// The other methods of a `LocalKey`, such as `set` and `get`, need no model,
+    /// since an opaque call already writes a tainted argument to, and reads from, the global memory of its arguments.
+    fn t_visit_local_key(
+        &mut self,
+        with: Instance<'tcx>,
+        args: &[Operand<'tcx>],
+        destination: &Place<'tcx>,
+        location: Location,
+    ) {
+        match (closure_argument(with), args) {
+            (Some(instance), [key, closure]) if self.t_is_analyzable(instance) => self
+                .t_fn_call_analysis(
+                    &[closure.clone(), key.clone()],
+                    instance,
+                    Some(destination),
+                    location,
+                    false,
+                ),
+            _ => self.t_opaque_call(args, destination),
+        }
+    }
+
+    fn t_resolve(&self, func: &Constant<'tcx>) -> (DefId, Option<Instance<'tcx>>) {
+        let (id, callee) = resolve_callee(self.tcx, self.instance, func);
+        (annotated(self.tcx, &self.env.info, id, callee), callee)
+    }
+
+    fn t_is_analyzable(&self, instance: Instance<'tcx>) -> bool {
+        is_analyzable(self.tcx, instance)
+    }
+
+    /// Dropping a value calls its destructor, and the destructors of its fields,
+    /// so we analyze it as a call to its `drop_in_place` with the dropped place as the argument.
+    fn t_visit_drop(&mut self, place: &Place<'tcx>, location: Location) {
+        let body = self.tcx.instance_mir(self.instance.def);
+        let ty = self.instance.subst_mir_and_normalize_erasing_regions(
+            self.tcx,
+            ParamEnv::reveal_all(),
+            ty::EarlyBinder::bind(place.ty(body, self.tcx).ty),
+        );
+        let instance = Instance::resolve_drop_in_place(self.tcx, ty);
+        if !self.t_is_analyzable(instance) {
+            return;
+        }
+
+        self.t_fn_call_analysis(&[Operand::Move(*place)], instance, None, location, false);
+
+        // A drop has no separate return edge, so both edges leave with the taint of either exit.
+        if let Some(effects) = self.returns.remove(&location.block) {
+            for (local, tainted) in effects {
+                if tainted {
+                    self.state.set_taint(local, true);
+                }
+            }
+        }
+    }
+
+    /// We do not analyze functions from other crates, since their MIR is generally unavailable.
+    /// Instead, we assume the result is tainted if any argument is,
+    /// and that any global memory an argument refers to may be reached through the result (e.g. `Mutex::lock`)
+    /// or be written to with the other arguments (e.g. `OnceLock::set` or `Sender::send`).
+    fn t_opaque_call(&mut self, args: &[Operand], destination: &Place) {
+        let places = args
+            .iter()
+            .filter_map(|arg| arg.place())
+            .collect::<Vec<_>>();
+
+        let tainted = places
+            .iter()
+            .any(|p| self.state.get_taint(p.local) || self.t_global_taint(p.local));
+        self.state.set_taint(destination.local, tainted);
+
+        for place in places {
+            if tainted {
+                self.t_taint_globals(place.local);
+            }
+            let refs = self.t_global_refs(place.local);
+            self.t_add_refs(destination.local, refs);
+        }
+    }
+
+    /// The taint of the arguments of a call, and the global memory they refer to.
+    fn t_init(&self, args: &[Operand<'tcx>]) -> (InitSet, InitRefs) {
         let init = args
             .iter()
             .map(|arg| match arg {
-                Operand::Copy(p) | Operand::Move(p) => Some(self.state.get_taint(p.local)),
-                Operand::Constant(_) => None,
+                Operand::Copy(p) | Operand::Move(p) => {
+                    Some(self.state.get_taint(p.local) || self.t_global_taint(p.local))
+                }
+                // Constants are clean, so the call can use a summary computed ahead of time.
+                Operand::Constant(_) => Some(false),
+            })
+            .collect::<Vec<_>>();
+        let init_refs = args
+            .iter()
+            .map(|arg| match arg.place() {
+                Some(p) => self.t_global_refs(p.local),
+                None => BTreeSet::new(),
             })
             .collect::<Vec<_>>();
 
-        let end_state = self.t_function_summary(id, init);
+        (init, init_refs)
+    }
 
-        if let Some(end_state) = end_state {
-            let return_place = Local::from_usize(0);
+    fn t_fn_call_analysis(
+        &mut self,
+        args: &[Operand<'tcx>],
+        instance: Instance<'tcx>,
+        destination: Option<&Place<'tcx>>,
+        location: Location,
+        catches_unwind: bool,
+    ) {
+        let key = self.t_context(args, instance, location);
+        // The callee may still be in progress, in which case its summary does not record it yet.
+        self.effects.deps.extend(body_deps(instance));
+        let init = key.1.clone();
+        let summary = TaintAnalysis::summarize(
+            self.tcx,
+            self.env,
+            key,
+            self.contexts.clone(),
+            self.globals.clone(),
+            self.findings.clone(),
+        );
 
-            if end_state.get_taint(return_place) {
-                self.t_visit_source_destination(destination);
-            }
+        let returns_taint = summary
+            .normal
+            .as_ref()
+            .map_or(false, |normal| normal.get_taint(Local::from_usize(0)));
+        if !self.reading {
+            let span = self.tcx.instance_mir(self.instance.def).source_info(location).span;
+            self.env.call_graph.call(
+                self.tcx,
+                (self.instance, self.init),
+                (instance.to_string(), &init),
+                span,
+                Some(returns_taint),
+            );
+        }
 
-            let target_body = self.tcx.optimized_mir(*id);
-            let arg_map = args
-                .iter()
-                .map(|arg| arg.place().or(None))
-                .zip(target_body.args_iter())
-                .collect::<Vec<_>>();
+        self.t_apply_summary(args, summary, destination, location, catches_unwind);
+    }
 
-            // Check if any variables which were passed in are tainted at this point.
-            for (caller_arg, callee_arg) in arg_map {
-                if let Some(place) = caller_arg {
-                    self.state
-                        .set_taint(place.local, end_state.get_taint(callee_arg));
-                }
+    /// The context to analyze a call in, which decides which other calls share its summary.
+    fn t_context(
+        &self,
+        args: &[Operand<'tcx>],
+        instance: Instance<'tcx>,
+        location: Location,
+    ) -> ContextKey<'tcx> {
+        let (init, init_refs) = self.t_init(args);
+        let policy = self.env.config.context;
+
+        let calls = match policy {
+            ContextSensitivity::CallString(k) if k > 0 => {
+                let mut calls = self.calls.clone();
+                calls.push((self.instance, location));
+                calls.drain(..calls.len().saturating_sub(k));
+                calls
+            }
+            _ => CallString::new(),
+        };
+        let receiver = match policy {
+            ContextSensitivity::Object if has_receiver(self.tcx, instance) => {
+                args.first().and_then(|arg| self.t_receiver(arg))
+            }
+            _ => None,
+        };
+        let mut key = (instance, init, init_refs, calls, receiver);
+
+        match policy {
+            ContextSensitivity::Arguments | ContextSensitivity::CallString(_) => {}
+            // Calls on the same object share a summary, whatever the taint of their arguments.
+            ContextSensitivity::Object | ContextSensitivity::Insensitive => {
+                self.contexts.merge(&mut key, |_| false)
             }
         }
+        key
     }
 
-    fn t_function_summary(&mut self, id: &DefId, init: Vec<Option<bool>>) -> Option<BitSet<Local>> {
-        let key = (*id, init.clone());
-
-        if let Some(summary) = self.t_get_cached_summary(&key) {
-            summary
-        } else {
-            // In the case that we have recursive or mutually recursive function calls,
-            // we make sure that we only compute a summary once per key by inserting None while we compute it.
-            // For subsequent calls, calling `t_function_summary` will simply return None and the visitor will analyze other branches.
-            self.t_insert_summary(&key, None);
-
-            let target_body = self.tcx.optimized_mir(*id);
-            let mut results =
-                TaintAnalysis::new_with_init(self.tcx, self.info, self.contexts.clone(), init)
-                    .into_engine(self.tcx, target_body)
-                    .pass_name("taint_analysis")
-                    .iterate_to_fixpoint()
-                    .into_results_cursor(target_body);
-
-            let state = if let Some((last, _)) = reverse_postorder(target_body).last() {
-                results.seek_to_block_end(last);
-                Some(results.get().clone())
-            } else {
-                None
-            };
-
-            // Once the function summary has been computed, we insert it into the cache.
-            self.t_insert_summary(&key, state.clone());
+    /// The object a method is called on, i.e. the locals of this function its receiver may refer to,
+    /// or the object this function was called on if the receiver is passed along.
+    fn t_receiver(&self, receiver: &Operand<'tcx>) -> Receiver<'tcx> {
+        let mut locals = BTreeSet::new();
+        let mut seen = HashSet::new();
+        let mut stack = vec![receiver.place()?.local];
+        while let Some(local) = stack.pop() {
+            if !seen.insert(local) {
+                continue;
+            }
+            match self.state.map.get(&local) {
+                Some(targets) if !targets.is_empty() => stack.extend(targets.iter().copied()),
+                _ => {
+                    locals.insert(local);
+                }
+            }
+        }
 
-            state
+        let own_receiver = BTreeSet::from([Local::from_usize(1)]);
+        if locals == own_receiver && self.receiver.is_some() {
+            return self.receiver.clone();
         }
+        Some((self.instance, locals))
     }
 
-    fn t_insert_summary(&mut self, key: &(DefId, Vec<Option<bool>>), val: Option<BitSet<Local>>) {
-        self.contexts.borrow_mut().insert(key.clone(), val);
+    /// Functions from other crates which were analyzed when they were compiled have summaries on disk,
+    /// which we use like those of our own functions.
+    /// Since we cannot point into their code, sinks they reach are reported at the call.
+    fn t_extern_call(
+        &mut self,
+        args: &[Operand<'tcx>],
+        instance: Instance<'tcx>,
+        destination: &Place<'tcx>,
+        span: &Span,
+        location: Location,
+    ) {
+        // A library is only summarized with no tainted arguments, and with each one tainted on its own,
+        // so a call with several tainted arguments gets the join of the summaries for each of them.
+        let summary = match instance.def {
+            ty::InstanceDef::Item(id) if instance.args.is_empty() => {
+                self.env.summaries.get(id, &self.t_init(args).0)
+            }
+            _ => None,
+        };
+
+        match summary {
+            Some(summary) => {
+                for sink in &summary.effects.sinks {
+                    self.t_report(sink.clone(), span);
+                }
+                self.globals
+                    .borrow_mut()
+                    .extend(summary.effects.globals.iter().copied());
+                self.t_apply_summary(args, summary, Some(destination), location, false);
+            }
+            None => self.t_opaque_call(args, destination),
+        }
     }
 
-    fn t_get_cached_summary(
+    fn t_apply_summary(
         &mut self,
-        key: &(DefId, Vec<Option<bool>>),
-    ) -> Option<Option<BitSet<Local>>> {
-        let contexts = self.contexts.borrow();
-        contexts.get(key).cloned()
+        args: &[Operand<'tcx>],
+        summary: Summary,
+        destination: Option<&Place<'tcx>>,
+        location: Location,
+        catches_unwind: bool,
+    ) {
+        let arg_map = args
+            .iter()
+            .zip((1..).map(Local::from_usize))
+            .filter_map(|(caller_arg, callee_arg)| Some((caller_arg.place()?.local, callee_arg)))
+            .collect::<Vec<_>>();
+
+        // If the callee unwinds, the caller continues in its cleanup block,
+        // so the taint of the arguments when unwinding is applied to the unwind edge.
+        if let Some(unwind) = &summary.unwind {
+            for &(caller_arg, callee_arg) in &arg_map {
+                let tainted = unwind.get_taint(callee_arg);
+                self.state.set_taint(caller_arg, tainted);
+                if tainted {
+                    self.t_taint_globals(caller_arg);
+                }
+            }
+        }
+        if summary.tainted_panic && !catches_unwind {
+            self.tainted_panic.set(true);
+        }
+        self.effects.join(&summary.effects);
+
+        // Check if any variables which were passed in are tainted once the call returns.
+        let mut effects = Vec::new();
+        let return_place = Local::from_usize(0);
+        let returns_taint = summary
+            .normal
+            .as_ref()
+            .map_or(false, |normal| normal.get_taint(return_place));
+        if let Some(destination) = destination {
+            effects.push((
+                destination.local,
+                returns_taint || (catches_unwind && summary.tainted_panic),
+            ));
+            self.t_add_refs(destination.local, summary.return_refs.clone());
+        }
+        // A caught panic returns normally, with the taint the closure had when it unwound.
+        let exits = if catches_unwind {
+            vec![&summary.normal, &summary.unwind]
+        } else {
+            vec![&summary.normal]
+        };
+        let exits = exits.into_iter().flatten().collect::<Vec<_>>();
+        if !exits.is_empty() {
+            for (caller_arg, callee_arg) in arg_map {
+                let tainted = exits.iter().any(|exit| exit.get_taint(callee_arg));
+                effects.push((caller_arg, tainted));
+                if tainted {
+                    self.t_taint_globals(caller_arg);
+                }
+            }
+        }
+        self.returns.insert(location.block, effects);
     }
 
     fn t_visit_source_destination(&mut self, destination: &Place) {
@@ -363,16 +1549,172 @@ where
         self.state.set_taint(destination.local, false);
     }
 
-    fn t_visit_sink(&mut self, name: String, args: &[Operand], span: &Span) {
-        if args.iter().map(|op| op.place()).any(|el| {
-            if let Some(place) = el {
-                self.state.get_taint(place.local)
-            } else {
-                false
+    fn t_visit_sink(&mut self, sink: Sink, args: &[Operand], span: &Span) {
+        let init = args
+            .iter()
+            .map(|op| Some(op.place().map_or(false, |place| self.state.get_taint(place.local))))
+            .collect::<InitSet>();
+        if !self.reading {
+            self.env.call_graph.call(
+                self.tcx,
+                (self.instance, self.init),
+                (sink.0.clone(), &init),
+                *span,
+                None,
+            );
+        }
+
+        if init.iter().any(|&t| t == Some(true)) {
+            self.t_report(sink, span);
+        }
+    }
+
+    /// A function with a parameter of a type marked `#[taint::sink_type]` is a sink for the argument given to it.
+    /// The methods of the type itself, including the trait implementations for it, are its own API, and are not.
+    ///
+    /// Only the parameters as they are declared count, so that a generic function such as `Vec::push`
+    /// or `mem::drop` is not a sink just because it is given the type,
+    /// and the functions of the standard library are never sinks for it.
+    fn t_visit_sink_types(
+        &mut self,
+        id: DefId,
+        callee: Option<Instance<'tcx>>,
+        name: &str,
+        args: &[Operand<'tcx>],
+        span: &Span,
+    ) {
+        let info = &self.env.info;
+        if info.sink_types.is_empty() || matches!(info.get_kind(&id), Some(AttrInfoKind::Sink)) {
+            return;
+        }
+        let callee = callee.map_or(id, |callee| callee.def_id());
+        if !matches!(self.tcx.def_kind(callee), DefKind::Fn | DefKind::AssocFn)
+            || matches!(
+                self.tcx.crate_name(callee.krate),
+                sym::std | sym::core | sym::alloc
+            )
+        {
+            return;
+        }
+        if let Some(impl_id) = self.tcx.impl_of_method(callee) {
+            if info
+                .sink_type(self.tcx.type_of(impl_id).skip_binder())
+                .is_some()
+            {
+                return;
             }
-        }) {
-            struct_span_err!(self.tcx.sess, *span, T0001, "function `{}` received tainted input", name)
-                .emit();
         }
+
+        let sig = self
+            .tcx
+            .erase_late_bound_regions(self.tcx.fn_sig(callee).instantiate_identity());
+        for (&param, arg) in sig.inputs().iter().zip(args) {
+            let Some(sink_type) = info.sink_type(param) else {
+                continue;
+            };
+            if arg.place().map_or(false, |place| self.state.get_taint(place.local)) {
+                let label = info.labels.get(&sink_type).cloned();
+                self.t_report((name.to_owned(), label), span);
+            }
+        }
+    }
+
+    fn t_report(&mut self, (name, label): Sink, span: &Span) {
+        if self.reading {
+            return;
+        }
+        self.effects.sinks.insert((name.clone(), label.clone()));
+
+        let finding = Finding {
+            name,
+            label,
+            span: *span,
+        };
+        if !self.effects.findings.contains(&finding) {
+            self.effects.findings.push(finding.clone());
+        }
+        let mut findings = self.findings.borrow_mut();
+        if !findings.contains(&finding) {
+            findings.push(finding);
+        }
+    }
+}
+
+/// Find the function that is actually called, given the generic arguments of the function we are in.
+/// This is what lets us follow calls through trait methods, such as `Future::poll` on an `async fn`.
+pub(crate) fn resolve_callee<'tcx>(
+    tcx: TyCtxt<'tcx>,
+    caller: Instance<'tcx>,
+    func: &Constant<'tcx>,
+) -> (DefId, Option<Instance<'tcx>>) {
+    let param_env = ParamEnv::reveal_all();
+    let ty = caller.subst_mir_and_normalize_erasing_regions(
+        tcx,
+        param_env,
+        ty::EarlyBinder::bind(func.literal.ty()),
+    );
+
+    match *ty.kind() {
+        TyKind::FnDef(id, args) => (
+            id,
+            Instance::resolve(tcx, param_env, id, args).ok().flatten(),
+        ),
+        _ => unreachable!("called a constant which is not a function: {:?}", ty),
+    }
+}
+
+/// The function whose annotation applies to a call of `id`, which resolved to `callee`.
+/// The implementations of a trait method inherit its annotation, unless they have one of their own or `#[taint::none]`,
+/// and calls which cannot be resolved, such as those through `dyn Trait`, get that of the trait method.
+pub(crate) fn annotated(
+    tcx: TyCtxt<'_>,
+    info: &AttrInfo,
+    id: DefId,
+    callee: Option<Instance<'_>>,
+) -> DefId {
+    let Some(callee) = callee.map(|callee| callee.def_id()) else {
+        return id;
+    };
+    if info.get_kind(&callee).is_some() || info.none.contains(&callee) {
+        return callee;
+    }
+    tcx.opt_associated_item(callee)
+        .and_then(|item| item.trait_item_def_id)
+        .filter(|trait_item| info.get_kind(trait_item).is_some())
+        .unwrap_or(id)
+}
+
+/// The closure given to a function we model as running it, which may be wrapped, e.g. in `AssertUnwindSafe`.
+fn closure_argument(instance: Instance<'_>) -> Option<Instance<'_>> {
+    instance
+        .args
+        .types()
+        .flat_map(|ty| ty.walk())
+        .filter_map(|arg| arg.as_type())
+        .find_map(|ty| match *ty.kind() {
+            TyKind::Closure(id, args) => Some(Instance::new(id, args)),
+            _ => None,
+        })
+}
+
+/// We only analyze the bodies of functions, closures and generators in the current crate.
+pub(crate) fn is_analyzable<'tcx>(tcx: TyCtxt<'tcx>, instance: Instance<'tcx>) -> bool {
+    match instance.def {
+        ty::InstanceDef::Item(id) => id.is_local() && tcx.is_mir_available(id),
+        // The drop glue of a type which actually needs dropping, which may call `Drop::drop`.
+        ty::InstanceDef::DropGlue(_, Some(_)) => true,
+        _ => false,
+    }
+}
+
+/// Whether the first argument of a function is the value it is called on,
+/// which is the case for methods and for the drop glue of a type.
+fn has_receiver<'tcx>(tcx: TyCtxt<'tcx>, instance: Instance<'tcx>) -> bool {
+    match instance.def {
+        ty::InstanceDef::Item(id) => {
+            tcx.def_kind(id) == DefKind::AssocFn && tcx.associated_item(id).fn_has_self_parameter
+        }
+        ty::InstanceDef::DropGlue(..) => true,
+        _ => false,
     }
 }
@@ -9,16 +9,20 @@ extern crate rustc_middle;
 extern crate rustc_session;
 extern crate rustc_span;
 
-use eval::main;
+use eval::{library, main};
 use rustc_driver::Compilation;
 use rustc_middle::ty::TyCtxt;
-use rustc_session::{config::ErrorOutputType, EarlyErrorHandler};
-use taint::eval;
+use rustc_session::{
+    config::{CrateType, ErrorOutputType},
+    EarlyErrorHandler,
+};
+use taint::eval::{self, config::TaintConfig, explain};
 use tracing_subscriber::{fmt::format::FmtSpan, EnvFilter};
 
 fn main() {
     rustc_driver::install_ice_hook("https://github.com/LiHRaM/taint/issues", |_| ());
-    rustc_driver::init_rustc_env_logger(&EarlyErrorHandler::new(ErrorOutputType::default()));
+    let handler = EarlyErrorHandler::new(ErrorOutputType::default());
+    rustc_driver::init_rustc_env_logger(&handler);
     init_tracing();
 
     let mut rustc_args: Vec<String> = vec![];
@@ -27,7 +31,32 @@ fn main() {
         rustc_args.push(arg);
     }
 
-    run_compiler(rustc_args, &mut TaintCompilerCallbacks)
+    if let Some(code) = explain_code(&rustc_args) {
+        match explain::find(&code) {
+            Some(explanation) => print!("{}", explanation),
+            None => handler.early_error(format!("{} is not a valid taint error code", code)),
+        }
+        return;
+    }
+
+    let config = TaintConfig::from_args(&mut rustc_args).unwrap_or_else(|e| handler.early_error(e));
+
+    run_compiler(rustc_args, &mut TaintCompilerCallbacks { config })
+}
+
+/// The code given to `--explain`, if it is one of ours.
+/// The codes of rustc are left for it to explain.
+fn explain_code(args: &[String]) -> Option<String> {
+    let mut args = args.iter();
+    while let Some(arg) = args.next() {
+        let code = match arg.strip_prefix("--explain") {
+            Some("") => args.next()?.clone(),
+            Some(code) => code.strip_prefix('=')?.to_owned(),
+            None => continue,
+        };
+        return explain::is_taint_code(&code).then_some(code);
+    }
+    None
 }
 
 /// We want our own tracing to debug the taint analysis.
@@ -64,6 +93,8 @@ fn run_compiler(mut args: Vec<String>, callbacks: &mut (dyn rustc_driver::Callba
     std::process::exit(exit_code)
 }
 
+// Only builds without rustup need `RUST_SYSROOT`, so it cannot be required with `env!`.
+#[allow(clippy::option_env_unwrap)]
 fn compile_time_sysroot() -> Option<String> {
     if option_env!("RUSTC_STAGE").is_some() {
         None
@@ -80,10 +111,19 @@ fn compile_time_sysroot() -> Option<String> {
 }
 
 /// Runs taint analysis once built-in analyses are complete.
-/// No artifacts are emitted, since this is meant to be an analysis tool only.
-struct TaintCompilerCallbacks;
+/// Only libraries are compiled further, since the crates which depend on them need their metadata.
+struct TaintCompilerCallbacks {
+    config: TaintConfig,
+}
 
 impl rustc_driver::Callbacks for TaintCompilerCallbacks {
+    /// Our diagnostics are lints, so that their levels can be set like those of any other lint.
+    /// The attributes of `taint-annotations` are only seen by the analysis once their markers are enabled.
+    fn config(&mut self, config: &mut rustc_interface::Config) {
+        config.register_lints = Some(Box::new(eval::lints::register));
+        eval::attributes::register_markers(config);
+    }
+
     /// All the work we do happens after analysis, so that we can make assumptions about the validity of the MIR.
     fn after_analysis<'tcx>(
         &mut self,
@@ -92,26 +132,34 @@ impl rustc_driver::Callbacks for TaintCompilerCallbacks {
         queries: &'tcx rustc_interface::Queries<'tcx>,
     ) -> Compilation {
         compiler.session().abort_if_errors();
-        enter_with_fn(queries, mir_analysis);
+        let compilation = enter_with_fn(queries, |tcx| mir_analysis(tcx, &self.config));
         compiler.session().abort_if_errors();
-        Compilation::Stop
+        compilation
     }
 }
 
 /// Call a function which takes the `TyCtxt`.
-fn enter_with_fn<'tcx, TyCtxtFn>(queries: &'tcx rustc_interface::Queries<'tcx>, enter_fn: TyCtxtFn)
+fn enter_with_fn<'tcx, TyCtxtFn, T>(
+    queries: &'tcx rustc_interface::Queries<'tcx>,
+    enter_fn: TyCtxtFn,
+) -> T
 where
-    TyCtxtFn: Fn(TyCtxt),
+    TyCtxtFn: Fn(TyCtxt) -> T,
 {
-    queries.global_ctxt().unwrap().enter(enter_fn);
+    queries.global_ctxt().unwrap().enter(enter_fn)
 }
 
 /// Perform the taint analysis.
-fn mir_analysis(tcx: TyCtxt) {
+fn mir_analysis(tcx: TyCtxt, config: &TaintConfig) -> Compilation {
     if let Some((entry_def_id, _)) = tcx.entry_fn(()) {
-        main::eval_main(tcx, entry_def_id);
+        main::eval_main(tcx, entry_def_id, config);
+        Compilation::Stop
+    } else if tcx.crate_types().iter().any(|&t| t != CrateType::Executable) {
+        library::eval_library(tcx, config);
+        Compilation::Continue
     } else {
         tcx.sess.struct_err("no main function found").emit();
         tcx.sess.abort_if_errors();
+        Compilation::Stop
     }
 }
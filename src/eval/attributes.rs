@@ -1,22 +1,81 @@
+use std::collections::HashMap;
+
 use hir::intravisit::Visitor;
-use rustc_ast::AttrKind;
-use rustc_errors::struct_span_err;
+use rustc_ast::{AttrItem, AttrKind, Attribute, MetaItemKind};
+use rustc_data_structures::fx::{FxHashMap, FxHashSet};
+use rustc_errors::error_code;
 use rustc_hir as hir;
-use rustc_hir::def_id::DefId;
-use rustc_middle::ty::TyCtxt;
-use rustc_span::Symbol;
+use rustc_hir::def::DefKind;
+use rustc_hir::def_id::{DefId, LocalDefId};
+use rustc_interface::DEFAULT_QUERY_PROVIDERS;
+use rustc_middle::ty::{GenericArgKind, RegisteredTools, Ty, TyCtxt, TyKind};
+use rustc_span::{
+    symbol::{kw, Ident},
+    Span, Symbol,
+};
+
+use super::lints;
+
+/// The tool the attributes of the `taint-annotations` crate leave a marker in,
+/// e.g. `#[taint::source]` adds `#[taint_marker::source]`.
+const MARKER_TOOL: &str = "taint_marker";
 
-/// Find all attributes in a crate which originate from the `taint` tool.
+/// The `cfg` the markers are behind, e.g. `#[cfg_attr(taint_analysis, taint_marker::source)]`.
+const MARKER_CFG: &str = "taint_analysis";
+
+/// Have the attributes of `taint-annotations` leave their markers, by setting the `cfg` they are behind,
+/// and register the tool of the markers, so that the crate being analyzed does not have to.
+pub fn register_markers(config: &mut rustc_interface::Config) {
+    config.crate_cfg.insert((MARKER_CFG.to_owned(), None));
+    config.override_queries = Some(|_, providers, _| {
+        providers.registered_tools = registered_tools;
+    });
+}
+
+fn registered_tools(tcx: TyCtxt<'_>, (): ()) -> RegisteredTools {
+    let mut tools = (DEFAULT_QUERY_PROVIDERS.registered_tools)(tcx, ());
+    tools.insert(Ident::from_str(MARKER_TOOL));
+    tools
+}
+
+/// Find all attributes in a crate which originate from the `taint` tool, or the markers of `taint-annotations`.
 pub struct TaintAttributeFinder<'tcx> {
     tcx: TyCtxt<'tcx>,
     pub(crate) info: AttrInfo,
+    /// The role given to each function, impl block and module, with its label,
+    /// which the functions without one of their own inherit from the innermost impl block or module they are in.
+    roles: FxHashMap<LocalDefId, (Symbol, Option<String>)>,
 }
 
-#[derive(Default, Debug)]
+#[derive(Clone, Default, Debug)]
 pub struct AttrInfo {
     pub sources: Vec<DefId>,
     pub sinks: Vec<DefId>,
     pub sanitizers: Vec<DefId>,
+    /// The labels of the sinks and sink types which have one, e.g. `#[taint::sink(label = "sql")]`.
+    pub labels: HashMap<DefId, String>,
+    /// The structs and enums whose values are always tainted, e.g. `#[taint::tainted_type(kind = "secret")]`.
+    pub tainted_types: Vec<DefId>,
+    /// The kinds of the tainted types which have one.
+    pub kinds: HashMap<DefId, String>,
+    /// The functions marked `#[taint::none]`, or in an impl block or module marked so,
+    /// which do not inherit the annotation of the trait method they implement.
+    pub none: Vec<DefId>,
+    /// The structs and enums which make every function with a parameter of their type a sink for it,
+    /// e.g. `#[taint::sink_type(label = "sql")]`.
+    pub sink_types: Vec<DefId>,
+    pub suppressions: Vec<Suppression>,
+}
+
+/// A function, statement or expression marked with `#[taint::allow(reason = "...")]`,
+/// in which findings have been acknowledged and are not reported.
+#[derive(Clone, Debug)]
+pub struct Suppression {
+    /// The code the findings are suppressed in.
+    pub scope: Span,
+    /// The attribute itself.
+    pub span: Span,
+    pub reason: String,
 }
 
 #[derive(Debug)]
@@ -38,6 +97,30 @@ impl AttrInfo {
             None
         }
     }
+
+    /// Whether values of a type are always tainted, because it is, refers to, or is generic over a tainted type.
+    pub fn is_tainted_type(&self, ty: Ty<'_>) -> bool {
+        find_adt(&self.tainted_types, ty).is_some()
+    }
+
+    /// The sink type a parameter of this type receives, if any, in the same sense as `is_tainted_type`.
+    pub fn sink_type(&self, ty: Ty<'_>) -> Option<DefId> {
+        find_adt(&self.sink_types, ty)
+    }
+}
+
+/// The first of `ids` which a type is, refers to, or is generic over.
+fn find_adt(ids: &[DefId], ty: Ty<'_>) -> Option<DefId> {
+    if ids.is_empty() {
+        return None;
+    }
+    ty.walk().find_map(|arg| match arg.unpack() {
+        GenericArgKind::Type(ty) => match ty.kind() {
+            TyKind::Adt(def, _) if ids.contains(&def.did()) => Some(def.did()),
+            _ => None,
+        },
+        _ => None,
+    })
 }
 
 impl<'tcx> TaintAttributeFinder<'tcx> {
@@ -45,32 +128,113 @@ impl<'tcx> TaintAttributeFinder<'tcx> {
         TaintAttributeFinder {
             tcx,
             info: AttrInfo::default(),
+            roles: FxHashMap::default(),
+        }
+    }
+
+    /// Give the functions the roles of the impl blocks and modules they are in, once every item has been visited.
+    ///
+    /// The crate itself is not an item, so its attributes are read here.
+    pub fn finish(mut self) -> AttrInfo {
+        self.visit_hir_id(hir::CRATE_HIR_ID);
+
+        let tcx = self.tcx;
+        for owner in tcx.hir_crate_items(()).owners() {
+            let def_id = owner.to_def_id();
+            if !matches!(tcx.def_kind(def_id), DefKind::Fn | DefKind::AssocFn) {
+                continue;
+            }
+            let role = std::iter::once(owner)
+                .chain(
+                    tcx.hir()
+                        .parent_owner_iter(hir::HirId::make_owner(owner.def_id))
+                        .map(|(scope, _)| scope),
+                )
+                .find_map(|scope| self.roles.get(&scope.def_id).cloned());
+            let Some((name, label)) = role else {
+                continue;
+            };
+
+            match name.as_str() {
+                "source" => self.info.sources.push(def_id),
+                "sink" => {
+                    self.info.sinks.push(def_id);
+                    if let Some(label) = label {
+                        self.info.labels.insert(def_id, label);
+                    }
+                }
+                "sanitizer" => self.info.sanitizers.push(def_id),
+                _ => self.info.none.push(def_id),
+            }
         }
+        self.info
     }
 }
 
 impl TaintAttributeFinder<'_> {
     fn visit_hir_id(&mut self, item_id: hir::HirId) {
-        let sym_source = Symbol::intern("source");
-        let sym_sink = Symbol::intern("sink");
-        let sym_sanitizer = Symbol::intern("sanitizer");
-
+        let tcx = self.tcx;
         let def_id = item_id.owner.to_def_id();
-        let attrs = self.tcx.hir().attrs(item_id);
-        for attr in attrs {
-            if let AttrKind::Normal(ref kind) = attr.kind {
-                let item = &kind.item;
-                if let Some(symbol) = get_taint_attr(item) {
-                    if symbol == &sym_source {
-                        self.info.sources.push(def_id)
-                    } else if symbol == &sym_sink {
-                        self.info.sinks.push(def_id)
-                    } else if symbol == &sym_sanitizer {
-                        self.info.sanitizers.push(def_id)
-                    } else {
-                        struct_span_err!(self.tcx.sess, item.span(), T0002, "Taint attribute `{}` is invalid. We currently only support `source`, `sink`, and `sanitizer`", symbol.to_ident_string()).emit();
-                    };
-                    break;
+        // The attributes the item was given so far, which must not be repeated,
+        // and of which a function is only given one role.
+        let mut given: Vec<(Symbol, Span)> = Vec::new();
+
+        for attr in tcx.hir().attrs(item_id) {
+            let AttrKind::Normal(ref kind) = attr.kind else {
+                continue;
+            };
+            let Some(name) = get_taint_attr(&kind.item) else {
+                continue;
+            };
+            let usage = match usage(name) {
+                // Suppressions may be on any statement or expression, so they are found separately.
+                Some(_) if name.as_str() == "allow" => continue,
+                Some(usage) => usage,
+                None => {
+                    invalid_attribute(tcx, item_id, &kind.item);
+                    continue;
+                }
+            };
+            if !placement(name).0.contains(&tcx.def_kind(def_id)) {
+                misplaced(tcx, item_id, attr.span, name, Some(tcx.def_span(def_id)));
+                continue;
+            }
+            let Some(arguments) = arguments(tcx, item_id, attr, name, usage) else {
+                continue;
+            };
+            let conflict = given.iter().find(|(first, _)| *first == name).or_else(|| {
+                given
+                    .iter()
+                    .find(|(first, _)| is_role(*first) && is_role(name))
+            });
+            if let Some(&first) = conflict {
+                conflicting_roles(tcx, item_id, attr.span, name, first);
+                continue;
+            }
+            given.push((name, attr.span));
+
+            let argument = |key: &str| {
+                arguments
+                    .iter()
+                    .find(|(k, _)| k.as_str() == key)
+                    .map(|(_, value)| value.to_string())
+            };
+            match name.as_str() {
+                "tainted_type" => {
+                    self.info.tainted_types.push(def_id);
+                    if let Some(kind) = argument("kind") {
+                        self.info.kinds.insert(def_id, kind);
+                    }
+                }
+                "sink_type" => {
+                    self.info.sink_types.push(def_id);
+                    if let Some(label) = argument("label") {
+                        self.info.labels.insert(def_id, label);
+                    }
+                }
+                _ => {
+                    self.roles
+                        .insert(item_id.owner.def_id, (name, argument("label")));
                 }
             }
         }
@@ -95,11 +259,264 @@ impl<'v> Visitor<'v> for TaintAttributeFinder<'_> {
     }
 }
 
-fn get_taint_attr(item: &rustc_ast::AttrItem) -> Option<&Symbol> {
-    if item.path.segments.len() == 2 && item.path.segments[0].ident.name == Symbol::intern("taint")
-    {
-        Some(&item.path.segments[1].ident.name)
-    } else {
+/// Find every `#[taint::allow]` in the crate, whichever code it is on.
+///
+/// The other attributes are only valid on items, which the finder visits,
+/// so they are rejected on anything else, such as statements or fields.
+pub fn find_suppressions(tcx: TyCtxt<'_>) -> Vec<Suppression> {
+    let mut suppressions = Vec::new();
+    // The attributes of an expression statement are on both the statement and the expression.
+    let mut seen = FxHashSet::default();
+
+    // The crate itself is not one of the items, but may have attributes too.
+    let owners = std::iter::once(hir::CRATE_OWNER_ID).chain(tcx.hir_crate_items(()).owners());
+    for owner in owners {
+        for &(local_id, attrs) in tcx.hir_attrs(owner).map.iter() {
+            let hir_id = hir::HirId { owner, local_id };
+            let is_item = local_id == hir::ItemLocalId::from_u32(0);
+            for attr in attrs.iter() {
+                let AttrKind::Normal(ref kind) = attr.kind else {
+                    continue;
+                };
+                let Some(name) = get_taint_attr(&kind.item) else {
+                    continue;
+                };
+                if !seen.insert(attr.span) {
+                    continue;
+                }
+                if name.as_str() != "allow" {
+                    match usage(name) {
+                        _ if is_item => {}
+                        Some(_) => misplaced(tcx, hir_id, attr.span, name, None),
+                        None => invalid_attribute(tcx, hir_id, &kind.item),
+                    }
+                    continue;
+                }
+
+                let Some(arguments) =
+                    arguments(tcx, hir_id, attr, name, usage(name).unwrap_or_default())
+                else {
+                    continue;
+                };
+                match arguments
+                    .into_iter()
+                    .find(|(key, _)| key.as_str() == "reason")
+                {
+                    Some((_, reason)) if !reason.as_str().trim().is_empty() => {
+                        suppressions.push(Suppression {
+                            scope: tcx.hir().span_with_body(hir_id),
+                            span: attr.span,
+                            reason: reason.to_string(),
+                        })
+                    }
+                    _ => {
+                        lints::emit(
+                            tcx,
+                            lints::INVALID_ATTRIBUTE,
+                            hir_id,
+                            attr.span,
+                            None,
+                            "`taint::allow` requires a reason".to_owned(),
+                            |diag| {
+                                diag.code(error_code!(T0003)).help(
+                                    "explain why the findings are safe, e.g. `#[taint::allow(reason = \"...\")]`",
+                                )
+                            },
+                        );
+                    }
+                }
+            }
+        }
+    }
+    suppressions
+}
+
+/// How an attribute is written, if it is one of ours.
+fn usage(name: Symbol) -> Option<&'static str> {
+    match name.as_str() {
+        "source" => Some("`#[taint::source]`"),
+        "sink" => Some("`#[taint::sink]` or `#[taint::sink(label = \"...\")]`"),
+        "sanitizer" => Some("`#[taint::sanitizer]`"),
+        "none" => Some("`#[taint::none]`"),
+        "sink_type" => Some("`#[taint::sink_type]` or `#[taint::sink_type(label = \"...\")]`"),
+        "tainted_type" => {
+            Some("`#[taint::tainted_type]` or `#[taint::tainted_type(kind = \"...\")]`")
+        }
+        "allow" => Some("`#[taint::allow(reason = \"...\")]`"),
+        _ => None,
+    }
+}
+
+/// The keys each attribute may be given, each with a string value.
+fn keys(name: Symbol) -> &'static [&'static str] {
+    match name.as_str() {
+        "sink" | "sink_type" => &["label"],
+        "allow" => &["reason"],
+        "tainted_type" => &["kind"],
+        _ => &[],
+    }
+}
+
+/// The arguments of an attribute, e.g. `label = "sql"`, or `None` if they are malformed.
+fn arguments(
+    tcx: TyCtxt<'_>,
+    hir_id: hir::HirId,
+    attr: &Attribute,
+    name: Symbol,
+    usage: &'static str,
+) -> Option<Vec<(Symbol, Symbol)>> {
+    let malformed = |span: Span, msg: String| {
+        lints::emit(
+            tcx,
+            lints::INVALID_ATTRIBUTE,
+            hir_id,
+            span,
+            None,
+            msg,
+            |diag| {
+                diag.code(error_code!(T0004))
+                    .help(format!("expected {}", usage))
+            },
+        );
         None
+    };
+
+    let list = match attr.meta().map(|meta| meta.kind) {
+        Some(MetaItemKind::Word) => return Some(Vec::new()),
+        Some(MetaItemKind::List(list)) => list,
+        _ => return malformed(attr.span, format!("malformed `taint::{}` attribute", name)),
+    };
+
+    let mut arguments: Vec<(Symbol, Symbol)> = Vec::new();
+    for nested in &list {
+        let Some(key) = nested
+            .ident()
+            .filter(|key| keys(name).contains(&key.name.as_str()))
+        else {
+            return malformed(
+                nested.span(),
+                format!("`taint::{}` does not take this argument", name),
+            );
+        };
+        let Some(value) = nested.value_str() else {
+            return malformed(
+                nested.span(),
+                format!("the `{}` of `taint::{}` must be a string", key, name),
+            );
+        };
+        if arguments.iter().any(|(k, _)| *k == key.name) {
+            return malformed(
+                nested.span(),
+                format!("the `{}` of `taint::{}` is given more than once", key, name),
+            );
+        }
+        arguments.push((key.name, value));
+    }
+    Some(arguments)
+}
+
+/// Report an attribute in the `taint` namespace which is not one of ours.
+fn invalid_attribute(tcx: TyCtxt<'_>, hir_id: hir::HirId, item: &AttrItem) {
+    let path = item
+        .path
+        .segments
+        .iter()
+        .skip(1)
+        .map(|segment| segment.ident.to_string())
+        .collect::<Vec<_>>()
+        .join("::");
+    lints::emit(
+        tcx,
+        lints::INVALID_ATTRIBUTE,
+        hir_id,
+        item.span(),
+        None,
+        format!("Taint attribute `{}` is invalid. We currently only support `source`, `sink`, `sanitizer`, `none`, `tainted_type`, `sink_type`, and `allow`", path),
+        |diag| diag.code(error_code!(T0002)),
+    );
+}
+
+/// Whether an attribute gives a function a role, of which it may only have one.
+/// A type may be both tainted and a sink type.
+fn is_role(name: Symbol) -> bool {
+    matches!(name.as_str(), "source" | "sink" | "sanitizer" | "none")
+}
+
+/// The kinds of items an attribute may be on, what to call them, and what to call anything else.
+fn placement(name: Symbol) -> (&'static [DefKind], &'static str, &'static str) {
+    match name.as_str() {
+        "tainted_type" | "sink_type" => (
+            &[DefKind::Struct, DefKind::Enum],
+            "structs and enums",
+            "not a struct or enum",
+        ),
+        _ => (
+            &[
+                DefKind::Fn,
+                DefKind::AssocFn,
+                DefKind::Impl { of_trait: false },
+                DefKind::Impl { of_trait: true },
+                DefKind::Mod,
+            ],
+            "functions, impl blocks and modules",
+            "not a function, impl block or module",
+        ),
+    }
+}
+
+/// Report an attribute on something it cannot be applied to.
+fn misplaced(tcx: TyCtxt<'_>, hir_id: hir::HirId, span: Span, name: Symbol, item: Option<Span>) {
+    let (_, targets, other) = placement(name);
+    lints::emit(
+        tcx,
+        lints::INVALID_ATTRIBUTE,
+        hir_id,
+        span,
+        None,
+        format!("`taint::{}` can only be applied to {}", name, targets),
+        |diag| {
+            if let Some(item) = item {
+                diag.span_label(item, other);
+            }
+            diag.code(error_code!(T0005))
+        },
+    );
+}
+
+/// Report a function given a second role, which the analysis would otherwise ignore.
+fn conflicting_roles(
+    tcx: TyCtxt<'_>,
+    hir_id: hir::HirId,
+    span: Span,
+    name: Symbol,
+    (first, first_span): (Symbol, Span),
+) {
+    let msg = if name == first {
+        format!("`taint::{}` is given more than once", name)
+    } else {
+        format!("`taint::{}` conflicts with `taint::{}`", name, first)
+    };
+    lints::emit(
+        tcx,
+        lints::INVALID_ATTRIBUTE,
+        hir_id,
+        span,
+        None,
+        msg,
+        |diag| {
+            diag.span_label(first_span, format!("`taint::{}` is given here", first))
+                .code(error_code!(T0006))
+        },
+    );
+}
+
+/// The name of an attribute in the `taint` namespace, or its marker, e.g. `sink` in `#[taint::sink]`.
+fn get_taint_attr(item: &AttrItem) -> Option<Symbol> {
+    let is_taint = |tool: &Ident| matches!(tool.name.as_str(), "taint" | MARKER_TOOL);
+    match &*item.path.segments {
+        [tool, name] if is_taint(&tool.ident) => Some(name.ident.name),
+        // A longer path, such as `taint::sink::label`, is not one of ours either.
+        [tool, ..] if is_taint(&tool.ident) => Some(kw::Empty),
+        _ => None,
     }
 }
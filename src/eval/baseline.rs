@@ -0,0 +1,170 @@
+//! A baseline of known findings, so that a crate which already has some can adopt the analysis and only fail on new ones.
+//!
+//! Given `--taint-baseline=<file>`, the findings are written to the file if it does not exist yet.
+//! Once it does, the findings it lists are no longer reported.
+//! Findings are told apart by their fingerprint, the function they are in, the sink they reach and the sources they come from,
+//! so that they still match once unrelated code moves them to other lines.
+//! The baseline lists a fingerprint once per finding, and as many findings are left out when it is read.
+//!
+//! The analysis does not track which source a taint came from, so the sources of the findings are found
+//! by analyzing the crate again for each source, with the other sources returning untainted values.
+//! This is only done when there is a baseline and something to find in it.
+//!
+//! The baseline is plain text, with one tab-separated line per finding, whose sources are separated by commas:
+//!
+//! ```text
+//! finding  <function> <sink> <sources>
+//! ```
+
+use std::{
+    collections::{BTreeSet, HashMap},
+    fs, io,
+    path::PathBuf,
+};
+
+use rustc_middle::ty::{Instance, TyCtxt};
+
+use crate::analysis::scheduler::summarize_all;
+use crate::taint_analysis::{Finding, TaintEnv};
+
+use super::lints::enclosing_body;
+
+type Fingerprint = (String, String, String);
+
+pub struct Baseline {
+    path: Option<PathBuf>,
+    /// How many findings with each fingerprint are known, if the baseline exists.
+    known: Option<HashMap<Fingerprint, usize>>,
+    /// The paths of the sources each finding comes from.
+    sources: HashMap<Finding, BTreeSet<String>>,
+    /// The findings to write to a new baseline.
+    found: Vec<Fingerprint>,
+}
+
+impl Baseline {
+    /// Load the baseline, if one was given, and find the sources of the findings of the analysis from `roots`.
+    pub fn load<'tcx>(
+        tcx: TyCtxt<'tcx>,
+        env: &TaintEnv,
+        roots: &[Instance<'tcx>],
+        exported: bool,
+        findings: &[Finding],
+    ) -> Self {
+        let path = env.config.baseline.as_ref();
+        let known = path.and_then(|path| match fs::read_to_string(path) {
+            Ok(contents) => Some(parse(&contents).unwrap_or_else(|| {
+                tcx.sess.warn(format!(
+                    "the taint baseline `{}` is malformed, so it was ignored",
+                    path.display()
+                ));
+                HashMap::new()
+            })),
+            Err(e) if e.kind() == io::ErrorKind::NotFound => None,
+            Err(e) => {
+                tcx.sess.warn(format!(
+                    "could not read the taint baseline `{}`: {}",
+                    path.display(),
+                    e
+                ));
+                Some(HashMap::new())
+            }
+        });
+
+        let mut sources: HashMap<Finding, BTreeSet<String>> = HashMap::new();
+        if path.is_some() && !findings.is_empty() {
+            for &source in env.info.sources.iter().chain(&env.info.tainted_types) {
+                let (_, found) = summarize_all(tcx, &env.only_source(source), roots, exported);
+                for finding in found.into_iter().filter(|f| findings.contains(f)) {
+                    sources
+                        .entry(finding)
+                        .or_default()
+                        .insert(tcx.def_path_str(source));
+                }
+            }
+        }
+
+        Baseline {
+            path: path.cloned(),
+            known,
+            sources,
+            found: Vec::new(),
+        }
+    }
+
+    /// Whether a finding is already known, in which case it is not reported.
+    pub fn contains(&mut self, tcx: TyCtxt<'_>, finding: &Finding) -> bool {
+        if self.path.is_none() {
+            return false;
+        }
+
+        let fingerprint = self.fingerprint(tcx, finding);
+        match &mut self.known {
+            Some(known) => match known.get_mut(&fingerprint) {
+                Some(count) if *count > 0 => {
+                    *count -= 1;
+                    true
+                }
+                _ => false,
+            },
+            // Every finding goes into a new baseline.
+            None => {
+                self.found.push(fingerprint);
+                true
+            }
+        }
+    }
+
+    /// Write the baseline if it did not exist yet.
+    pub fn store(&mut self, tcx: TyCtxt<'_>) {
+        let (Some(path), None) = (&self.path, &self.known) else {
+            return;
+        };
+
+        self.found.sort();
+        let mut contents = String::new();
+        for (function, sink, sources) in &self.found {
+            contents.push_str(&format!("finding\t{}\t{}\t{}\n", function, sink, sources));
+        }
+        match fs::write(path, contents) {
+            Ok(()) => tcx.sess.note_without_error(format!(
+                "wrote {} findings to the taint baseline `{}`",
+                self.found.len(),
+                path.display()
+            )),
+            Err(e) => tcx.sess.warn(format!(
+                "could not write the taint baseline to `{}`: {}",
+                path.display(),
+                e
+            )),
+        }
+    }
+
+    /// The function a finding is in, the sink it reaches, and the sources it comes from.
+    fn fingerprint(&self, tcx: TyCtxt<'_>, finding: &Finding) -> Fingerprint {
+        let function = enclosing_body(tcx, finding.span)
+            .map(|id| tcx.def_path_str(id.to_def_id()))
+            .unwrap_or_default();
+        let sources = self
+            .sources
+            .get(finding)
+            .map(|sources| sources.iter().cloned().collect::<Vec<_>>().join(","))
+            .unwrap_or_default();
+
+        (function, finding.name.clone(), sources)
+    }
+}
+
+fn parse(contents: &str) -> Option<HashMap<Fingerprint, usize>> {
+    let mut known = HashMap::new();
+    for line in contents.lines().filter(|line| !line.is_empty()) {
+        let mut fields = line.split('\t');
+        if fields.next()? != "finding" {
+            return None;
+        }
+        let function = fields.next()?.to_owned();
+        let sink = fields.next()?.to_owned();
+        let sources = fields.next()?.to_owned();
+        *known.entry((function, sink, sources)).or_default() += 1;
+    }
+    Some(known)
+}
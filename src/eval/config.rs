@@ -0,0 +1,175 @@
+//! Options for the taint analysis, which are passed to the driver alongside the usual rustc arguments.
+
+use std::{any::TypeId, collections::BTreeMap, path::PathBuf, sync::Arc};
+
+use rustc_data_structures::sync::Lrc;
+
+/// Configures the taint analysis.
+///
+/// Each option is given on the command line as `--taint-<option>`, e.g. `--taint-panic-sinks`.
+#[derive(Debug, Default, Clone)]
+pub struct TaintConfig {
+    /// Report panics whose message or payload may be tainted, as if the panic were a sink.
+    pub panic_sinks: bool,
+    /// How calls to the same function are told apart, given as `--taint-context=<policy>`.
+    pub context: ContextSensitivity,
+    /// The most basic blocks a single analysis of a function may visit, given as `--taint-max-blocks=<n>`.
+    pub max_blocks: Option<usize>,
+    /// How many analyses of callees may be nested in each other, given as `--taint-max-depth=<n>`.
+    pub max_depth: Option<usize>,
+    /// The most basic blocks the whole analysis may visit, given as `--taint-max-total-blocks=<n>`.
+    pub max_total_blocks: Option<usize>,
+    /// How many seconds the whole analysis may take, given as `--taint-timeout=<seconds>`.
+    pub timeout: Option<u64>,
+    /// Where to write the call graph discovered by the analysis, given as `--taint-call-graph=<file>`.
+    pub call_graph: Option<PathBuf>,
+    /// The path of a function whose MIR to write with its taint, given as `--taint-dump-mir=<fn-path>`.
+    pub dump_mir: Option<String>,
+    /// Where to write the findings as JSON, including those which were suppressed, given as `--taint-report=<file>`.
+    pub report: Option<PathBuf>,
+    /// The findings which are already known, and not reported, given as `--taint-baseline=<file>`.
+    /// If the file does not exist yet, the findings are written to it instead.
+    pub baseline: Option<PathBuf>,
+    /// How severe findings at sinks with a label are, given as `--taint-severity=<label>=<allow|warn|deny>`.
+    pub severities: BTreeMap<String, Severity>,
+    /// Where to write every use of `taint::declassify!` as JSON, given as `--taint-report-declassifications=<file>`.
+    pub declassifications: Option<PathBuf>,
+}
+
+/// Which calls to a function share a summary.
+///
+/// The arguments of the calls which share a summary are merged, so the summary covers the taint of every one of them.
+/// Telling more calls apart is more precise, but means analyzing the same function more often.
+#[derive(Debug, Default, Clone, Copy, PartialEq, Eq)]
+pub enum ContextSensitivity {
+    /// Every call shares one summary (`insensitive`).
+    Insensitive,
+    /// Calls share a summary if the same arguments are tainted (`arguments`).
+    #[default]
+    Arguments,
+    /// Calls share a summary if the same arguments are tainted,
+    /// and the last `k` calls leading to them were made from the same places (`call-string=<k>`).
+    CallString(usize),
+    /// Calls to methods share a summary if they are made on the same object (`object`),
+    /// i.e. their receivers refer to the same locals of the same function.
+    /// Calls to other functions share one summary.
+    Object,
+}
+
+impl ContextSensitivity {
+    fn parse(policy: &str) -> Option<Self> {
+        match policy {
+            "insensitive" => Some(ContextSensitivity::Insensitive),
+            "arguments" => Some(ContextSensitivity::Arguments),
+            "object" => Some(ContextSensitivity::Object),
+            _ => Some(ContextSensitivity::CallString(
+                policy.strip_prefix("call-string=")?.parse().ok()?,
+            )),
+        }
+    }
+}
+
+/// The level of the `taint::tainted_sink` lint for findings at sinks with some label,
+/// unless the lint was given a level on the command line or in the code.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Severity {
+    Allow,
+    Warn,
+    Deny,
+}
+
+impl Severity {
+    /// Parse `<label>=<level>`.
+    fn parse(option: &str) -> Option<(String, Self)> {
+        let (label, level) = option.rsplit_once('=')?;
+        let severity = match level {
+            "allow" => Severity::Allow,
+            "warn" => Severity::Warn,
+            "deny" => Severity::Deny,
+            _ => return None,
+        };
+        Some((label.to_owned(), severity))
+    }
+}
+
+impl TaintConfig {
+    /// Remove our own options from `args`, leaving the ones meant for rustc.
+    ///
+    /// `--taint-threads=<n>` is the exception, as the analysis runs on the compiler's own threads.
+    /// It is passed on as `-Zthreads=<n>`, and more than one thread is rejected
+    /// unless the compiler was built with the parallel front-end, since the analysis would still run on one.
+    pub fn from_args(args: &mut Vec<String>) -> Result<Self, String> {
+        let mut config = TaintConfig::default();
+        let mut rustc_args = Vec::with_capacity(args.len());
+
+        for arg in args.drain(..) {
+            match arg.strip_prefix("--taint-") {
+                Some("panic-sinks") => config.panic_sinks = true,
+                Some(option) if option.starts_with("context=") => {
+                    config.context = ContextSensitivity::parse(&option["context=".len()..])
+                        .ok_or_else(|| format!("unknown context sensitivity in `{}`", arg))?;
+                }
+                Some(option) if option.starts_with("max-blocks=") => {
+                    config.max_blocks = Some(number(&arg, "max-blocks=")?);
+                }
+                Some(option) if option.starts_with("max-depth=") => {
+                    config.max_depth = Some(number(&arg, "max-depth=")?);
+                }
+                Some(option) if option.starts_with("max-total-blocks=") => {
+                    config.max_total_blocks = Some(number(&arg, "max-total-blocks=")?);
+                }
+                Some(option) if option.starts_with("timeout=") => {
+                    config.timeout = Some(number(&arg, "timeout=")?);
+                }
+                Some(option) if option.starts_with("call-graph=") => {
+                    config.call_graph = Some(PathBuf::from(&option["call-graph=".len()..]));
+                }
+                Some(option) if option.starts_with("dump-mir=") => {
+                    config.dump_mir = Some(option["dump-mir=".len()..].to_owned());
+                }
+                Some(option) if option.starts_with("report-declassifications=") => {
+                    config.declassifications =
+                        Some(PathBuf::from(&option["report-declassifications=".len()..]));
+                }
+                Some(option) if option.starts_with("report=") => {
+                    config.report = Some(PathBuf::from(&option["report=".len()..]));
+                }
+                Some(option) if option.starts_with("baseline=") => {
+                    config.baseline = Some(PathBuf::from(&option["baseline=".len()..]));
+                }
+                Some(option) if option.starts_with("severity=") => {
+                    let (label, severity) = Severity::parse(&option["severity=".len()..])
+                        .ok_or_else(|| format!("invalid severity in `{}`", arg))?;
+                    config.severities.insert(label, severity);
+                }
+                Some(option) if option.starts_with("threads=") => {
+                    let threads: usize = number(&arg, "threads=")?;
+                    if threads > 1 && !parallel_compiler() {
+                        return Err(format!(
+                            "`{}` needs a compiler built with the parallel front-end, which this one is not",
+                            arg
+                        ));
+                    }
+                    rustc_args.push(format!("-Zthreads={}", threads));
+                }
+                Some(_) => return Err(format!("unknown taint option `{}`", arg)),
+                None => rustc_args.push(arg),
+            }
+        }
+
+        *args = rustc_args;
+        Ok(config)
+    }
+}
+
+/// Whether the compiler was built with the parallel front-end, which is the only case where its reference counts are atomic.
+fn parallel_compiler() -> bool {
+    TypeId::of::<Lrc<()>>() == TypeId::of::<Arc<()>>()
+}
+
+/// The number given to an option, e.g. `--taint-max-depth=<n>`.
+fn number<T: std::str::FromStr>(arg: &str, option: &str) -> Result<T, String> {
+    arg["--taint-".len() + option.len()..]
+        .parse()
+        .map_err(|_| format!("invalid number in `{}`", arg))
+}
@@ -0,0 +1,27 @@
+//! Long-form explanations of the error codes of the analysis, printed by `taint --explain <code>`.
+//!
+//! Each explanation is a markdown file in `codes/`, in the style of those of rustc.
+
+/// Every error code the analysis emits, with its explanation.
+pub const CODES: &[(&str, &str)] = &[
+    ("T0001", include_str!("codes/T0001.md")),
+    ("T0002", include_str!("codes/T0002.md")),
+    ("T0003", include_str!("codes/T0003.md")),
+    ("T0004", include_str!("codes/T0004.md")),
+    ("T0005", include_str!("codes/T0005.md")),
+    ("T0006", include_str!("codes/T0006.md")),
+    ("T0007", include_str!("codes/T0007.md")),
+];
+
+/// The explanation of an error code, which may be given in lowercase.
+pub fn find(code: &str) -> Option<&'static str> {
+    CODES
+        .iter()
+        .find(|(c, _)| c.eq_ignore_ascii_case(code))
+        .map(|(_, explanation)| *explanation)
+}
+
+/// Whether a code is one of ours, rather than one of rustc, which explains its own.
+pub fn is_taint_code(code: &str) -> bool {
+    code.starts_with(['T', 't'])
+}
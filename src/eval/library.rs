@@ -0,0 +1,35 @@
+use rustc_hir::def::DefKind;
+use rustc_middle::ty::{Instance, TyCtxt};
+
+use crate::eval::{baseline::Baseline, config::TaintConfig, report};
+use crate::analysis::scheduler::summarize_all;
+use crate::summaries::ExternSummaries;
+use crate::taint_analysis::TaintEnv;
+
+/// A library has no entry point, so we analyze each of its functions as if it could be called from another crate.
+/// The summaries are written to disk for the crates which depend on it.
+pub fn eval_library(tcx: TyCtxt<'_>, config: &TaintConfig) {
+    let env = TaintEnv::new(tcx, config);
+
+    // Only functions without generic parameters can be summarized on their own.
+    let functions = tcx
+        .hir()
+        .body_owners()
+        .filter(|&id| matches!(tcx.def_kind(id), DefKind::Fn | DefKind::AssocFn))
+        .filter(|&id| tcx.generics_of(id).count() == 0)
+        .map(|id| Instance::mono(tcx, id.to_def_id()))
+        .collect::<Vec<_>>();
+
+    let (contexts, findings) = summarize_all(tcx, &env, &functions, true);
+
+    let baseline = Baseline::load(tcx, &env, &functions, true, &findings);
+    report::emit(tcx, &env, &findings, baseline);
+    env.assertions.emit_errors(tcx);
+    env.budget.emit_warnings(tcx);
+
+    ExternSummaries::store(tcx, &env.info, &contexts);
+    env.call_graph.store(tcx);
+    env.mir_dump.finish(tcx);
+    env.declassifications.store(tcx);
+    env.cache.store(tcx);
+}
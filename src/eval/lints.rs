@@ -0,0 +1,125 @@
+//! The diagnostics of the analysis are lints in the `taint` tool namespace,
+//! so that they can be allowed or made warnings like any other lint,
+//! with `-A`, `-W` and `-D` on the command line or `#[allow]`, `#[warn]` and `#[deny]` in the code.
+//!
+//! Findings may also get a severity from the label of their sink (`--taint-severity=<label>=<level>`),
+//! which replaces the default level of `taint::tainted_sink` but not a level set on the command line or in the code.
+
+use rustc_errors::DiagnosticBuilder;
+use rustc_hir::{
+    def_id::LocalDefId,
+    intravisit::{self, Visitor},
+    HirId,
+};
+use rustc_lint::LintStore;
+use rustc_middle::{
+    lint::{struct_lint_level, LintLevelSource},
+    ty::TyCtxt,
+};
+use rustc_session::{
+    declare_tool_lint,
+    lint::{builtin::WARNINGS, Level, Lint},
+    Session,
+};
+use rustc_span::Span;
+
+use super::config::Severity;
+
+declare_tool_lint! {
+    /// A sink may receive tainted input.
+    pub taint::TAINTED_SINK,
+    Deny,
+    "a sink may receive tainted input",
+    report_in_external_macro: true
+}
+
+declare_tool_lint! {
+    /// An attribute in the `taint` namespace is not one we support, or is missing arguments.
+    pub taint::INVALID_ATTRIBUTE,
+    Deny,
+    "an attribute in the `taint` namespace is invalid"
+}
+
+/// Make the lints known to the compiler, so that their levels can be set.
+pub fn register(_: &Session, store: &mut LintStore) {
+    store.register_lints(&[TAINTED_SINK, INVALID_ATTRIBUTE]);
+}
+
+/// Emit a lint at the level it has at `hir_id`, or with `severity` if its level was not set anywhere.
+pub(crate) fn emit(
+    tcx: TyCtxt<'_>,
+    lint: &'static Lint,
+    hir_id: HirId,
+    span: Span,
+    severity: Option<Severity>,
+    msg: String,
+    decorate: impl for<'a, 'b> FnOnce(
+        &'b mut DiagnosticBuilder<'a, ()>,
+    ) -> &'b mut DiagnosticBuilder<'a, ()>,
+) {
+    let (mut level, src) = tcx.lint_level_at_node(lint, hir_id);
+    if let (Some(severity), LintLevelSource::Default) = (severity, &src) {
+        level = match severity {
+            Severity::Allow => Level::Allow,
+            Severity::Warn => match tcx.lint_level_at_node(WARNINGS, hir_id).0 {
+                // `-D warnings` or `#[allow(warnings)]` apply to warnings from a label as well.
+                Level::Warn => Level::Warn,
+                warnings => warnings,
+            },
+            Severity::Deny => Level::Deny,
+        };
+        level = level.min(tcx.sess.opts.lint_cap.unwrap_or(Level::Forbid));
+    }
+    struct_lint_level(tcx.sess, lint, level, src, Some(span.into()), msg, decorate);
+}
+
+/// The innermost function, closure or constant whose body contains a span.
+pub(crate) fn enclosing_body(tcx: TyCtxt<'_>, span: Span) -> Option<LocalDefId> {
+    let span = span.source_callsite();
+    let hir = tcx.hir();
+    hir.body_owners()
+        .map(|id| (id, hir.span_with_body(hir.local_def_id_to_hir_id(id))))
+        .filter(|(_, body)| body.contains(span))
+        .min_by_key(|(_, body)| body.hi() - body.lo())
+        .map(|(id, _)| id)
+}
+
+/// The innermost expression, statement or function around a span, whose lint levels apply to it.
+pub(crate) fn enclosing_node(tcx: TyCtxt<'_>, span: Span) -> Option<HirId> {
+    let owner = enclosing_body(tcx, span)?;
+    let hir = tcx.hir();
+    let mut finder = InnermostNode {
+        span: span.source_callsite(),
+        found: hir.local_def_id_to_hir_id(owner),
+    };
+    finder.visit_body(hir.body(hir.body_owned_by(owner)));
+    Some(finder.found)
+}
+
+struct InnermostNode {
+    span: Span,
+    found: HirId,
+}
+
+impl<'v> Visitor<'v> for InnermostNode {
+    fn visit_expr(&mut self, expr: &'v rustc_hir::Expr<'v>) {
+        if expr.span.contains(self.span) {
+            self.found = expr.hir_id;
+            intravisit::walk_expr(self, expr);
+        }
+    }
+
+    fn visit_stmt(&mut self, stmt: &'v rustc_hir::Stmt<'v>) {
+        if stmt.span.contains(self.span) {
+            self.found = stmt.hir_id;
+            intravisit::walk_stmt(self, stmt);
+        }
+    }
+
+    fn visit_local(&mut self, local: &'v rustc_hir::Local<'v>) {
+        if local.span.contains(self.span) {
+            self.found = local.hir_id;
+            intravisit::walk_local(self, local);
+        }
+    }
+}
@@ -1,19 +1,22 @@
 use rustc_hir::def_id::DefId;
-use rustc_middle::ty::TyCtxt;
-use rustc_mir_dataflow::Analysis;
+use rustc_middle::ty::{Instance, TyCtxt};
 
-use crate::eval::attributes::TaintAttributeFinder;
-use crate::taint_analysis::TaintAnalysis;
+use crate::eval::{baseline::Baseline, config::TaintConfig, report};
+use crate::analysis::scheduler::summarize_all;
+use crate::taint_analysis::TaintEnv;
 
-pub fn eval_main(tcx: TyCtxt<'_>, main_id: DefId) {
-    // Find all functions in the current crate that have been tagged
-    let mut finder = TaintAttributeFinder::new(tcx);
-    tcx.hir().visit_all_item_likes_in_crate(&mut finder);
+pub fn eval_main(tcx: TyCtxt<'_>, main_id: DefId, config: &TaintConfig) {
+    let env = TaintEnv::new(tcx, config);
 
-    let entry = tcx.optimized_mir(main_id);
+    let roots = [Instance::mono(tcx, main_id)];
+    let (_, findings) = summarize_all(tcx, &env, &roots, false);
 
-    let _ = TaintAnalysis::new(tcx, &finder.info)
-        .into_engine(tcx, entry)
-        .pass_name("taint_analysis")
-        .iterate_to_fixpoint();
+    let baseline = Baseline::load(tcx, &env, &roots, false, &findings);
+    report::emit(tcx, &env, &findings, baseline);
+    env.assertions.emit_errors(tcx);
+    env.budget.emit_warnings(tcx);
+    env.call_graph.store(tcx);
+    env.mir_dump.finish(tcx);
+    env.declassifications.store(tcx);
+    env.cache.store(tcx);
 }
@@ -1,4 +1,10 @@
 //! Logic for running the taint analysis
 
 pub mod attributes;
+pub mod baseline;
+pub mod config;
+pub mod explain;
+pub mod library;
+pub mod lints;
 pub mod main;
+pub mod report;
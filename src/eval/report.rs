@@ -0,0 +1,144 @@
+//! Reports the findings of the analysis, except for those in code marked with `#[taint::allow(reason = "...")]`.
+//!
+//! A finding is suppressed by the innermost `taint::allow` around the call to the sink,
+//! and a `taint::allow` which suppresses nothing gets a warning, so that stale ones are removed.
+//! Findings in the baseline (`--taint-baseline`) are not reported either.
+//! Given `--taint-report=<file>`, the findings are also written as JSON,
+//! along with the suppressed ones and the reasons they were suppressed for, and those in the baseline.
+
+use std::fs;
+
+use rustc_middle::ty::TyCtxt;
+use rustc_span::Span;
+
+use crate::taint_analysis::{Finding, TaintEnv};
+
+use super::baseline::Baseline;
+
+pub fn emit(tcx: TyCtxt<'_>, env: &TaintEnv, findings: &[Finding], mut baseline: Baseline) {
+    let suppressions = &env.info.suppressions;
+    let mut used = vec![false; suppressions.len()];
+    let mut reported = Vec::new();
+    let mut suppressed = Vec::new();
+    let mut baselined = Vec::new();
+
+    for finding in findings {
+        let span = finding.span.source_callsite();
+        let innermost = suppressions
+            .iter()
+            .enumerate()
+            .filter(|(_, s)| s.scope.contains(span))
+            .min_by_key(|(_, s)| s.scope.hi() - s.scope.lo());
+        match innermost {
+            Some((i, _)) => {
+                used[i] = true;
+                suppressed.push((finding, i));
+            }
+            None if baseline.contains(tcx, finding) => baselined.push(finding),
+            None => {
+                finding.emit(tcx, &env.config);
+                reported.push(finding);
+            }
+        }
+    }
+    baseline.store(tcx);
+
+    for (suppression, _) in suppressions.iter().zip(&used).filter(|(_, &used)| !used) {
+        tcx.sess
+            .struct_span_warn(
+                suppression.span,
+                "unused `taint::allow`: no findings were suppressed",
+            )
+            .note(format!("it was added because: {}", suppression.reason))
+            .emit();
+    }
+
+    let Some(path) = &env.config.report else {
+        return;
+    };
+    let format_finding = |finding: &&Finding| {
+        format!(
+            "    {{\"sink\": \"{}\", \"span\": \"{}\"}}",
+            escape(&finding.name),
+            escape(&format_span(tcx, finding.span))
+        )
+    };
+    let findings = reported.iter().map(format_finding).collect::<Vec<_>>();
+    let baselined = baselined.iter().map(format_finding).collect::<Vec<_>>();
+    let suppressed = suppressed
+        .iter()
+        .map(|(finding, i)| {
+            format!(
+                "    {{\"sink\": \"{}\", \"span\": \"{}\", \"allow\": \"{}\", \"reason\": \"{}\"}}",
+                escape(&finding.name),
+                escape(&format_span(tcx, finding.span)),
+                escape(&format_span(tcx, suppressions[*i].span)),
+                escape(&suppressions[*i].reason)
+            )
+        })
+        .collect::<Vec<_>>();
+    let unused = suppressions
+        .iter()
+        .zip(&used)
+        .filter(|(_, &used)| !used)
+        .map(|(suppression, _)| {
+            format!(
+                "    {{\"allow\": \"{}\", \"reason\": \"{}\"}}",
+                escape(&format_span(tcx, suppression.span)),
+                escape(&suppression.reason)
+            )
+        })
+        .collect::<Vec<_>>();
+
+    let contents = format!(
+        "{{\n  \"findings\": {},\n  \"suppressed\": {},\n  \"unused_suppressions\": {},\n  \"baselined\": {}\n}}\n",
+        json_array(&findings),
+        json_array(&suppressed),
+        json_array(&unused),
+        json_array(&baselined)
+    );
+    if let Err(e) = fs::write(path, contents) {
+        tcx.sess.warn(format!(
+            "could not write the taint report to `{}`: {}",
+            path.display(),
+            e
+        ));
+    }
+}
+
+/// Where a span starts, as `<file>:<line>:<column>`.
+pub(crate) fn format_span(tcx: TyCtxt<'_>, span: Span) -> String {
+    let position = tcx.sess.source_map().lookup_char_pos(span.lo());
+    format!(
+        "{}:{}:{}",
+        position.file.name.prefer_local(),
+        position.line,
+        position.col_display + 1
+    )
+}
+
+/// A JSON array of values which have already been formatted, one per line.
+pub(crate) fn json_array(values: &[String]) -> String {
+    if values.is_empty() {
+        "[]".to_owned()
+    } else {
+        format!("[\n{}\n  ]", values.join(",\n"))
+    }
+}
+
+/// Escape a string for a quoted string in JSON or Graphviz DOT.
+pub(crate) fn escape(s: &str) -> String {
+    let mut escaped = String::with_capacity(s.len());
+    for c in s.chars() {
+        match c {
+            '"' | '\\' => {
+                escaped.push('\\');
+                escaped.push(c);
+            }
+            '\n' => escaped.push_str("\\n"),
+            c if c.is_control() => escaped.push_str(&format!("\\u{:04x}", c as u32)),
+            c => escaped.push(c),
+        }
+    }
+    escaped
+}
@@ -10,7 +10,9 @@ extern crate rustc_fluent_macro;
 extern crate rustc_hir;
 extern crate rustc_index;
 extern crate rustc_interface;
+extern crate rustc_lint;
 extern crate rustc_macros;
+extern crate rustc_metadata;
 extern crate rustc_middle;
 extern crate rustc_mir_dataflow;
 extern crate rustc_session;
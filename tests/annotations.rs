@@ -0,0 +1,131 @@
+//! Test that the attributes of the `taint-annotations` crate are seen by the analysis,
+//! in a crate which imports it as `taint` rather than registering the `taint` tool.
+
+use std::{
+    env, fs,
+    path::{Path, PathBuf},
+    process::Command,
+};
+
+const PROGRAM: &str = r#"
+fn main() {
+    let val = input();
+    output(val);
+    output(escape(val));
+    output(taint::declassify!(val, "for the test"));
+    taint::assert_tainted!(val);
+    allowed(val);
+
+    let secret = Secret(1);
+    Logger.log(secret.0);
+}
+
+#[taint::source]
+fn input() -> i32 {
+    15
+}
+
+#[taint::sink(label = "output")]
+fn output(_: i32) {}
+
+#[taint::sanitizer]
+fn escape(val: i32) -> i32 {
+    val
+}
+
+#[taint::allow(reason = "for the test")]
+fn allowed(val: i32) {
+    output(val);
+}
+
+#[taint::tainted_type]
+struct Secret(i32);
+
+struct Logger;
+
+#[taint::sink]
+impl Logger {
+    fn log(&self, _: i32) {}
+}
+"#;
+
+#[test]
+fn attributes_are_analyzed() {
+    let dir = env::temp_dir().join(format!("taint-annotations-{}", std::process::id()));
+    let _ = fs::remove_dir_all(&dir);
+    fs::create_dir_all(&dir).unwrap();
+    fs::write(dir.join("main.rs"), PROGRAM).unwrap();
+
+    compile(
+        &dir,
+        "annotations/macros/src/lib.rs",
+        &[
+            "--crate-type",
+            "proc-macro",
+            "--crate-name",
+            "taint_annotations_macros",
+        ],
+    );
+    compile(
+        &dir,
+        "annotations/src/lib.rs",
+        &[
+            "--crate-type",
+            "lib",
+            "--crate-name",
+            "taint_annotations",
+            "--extern",
+            "taint_annotations_macros",
+        ],
+    );
+
+    let output = Command::new(taint_path())
+        .current_dir(&dir)
+        .arg("main.rs")
+        .args(["--edition", "2018", "-L", "."])
+        .args(["--extern", "taint=libtaint_annotations.rlib"])
+        .output()
+        .expect("failed to run taint");
+    let stderr = String::from_utf8(output.stderr).unwrap();
+    assert!(!output.status.success(), "{}", stderr);
+
+    let findings = stderr
+        .lines()
+        .filter(|line| line.starts_with("error[T"))
+        .collect::<Vec<_>>();
+    assert_eq!(
+        findings,
+        [
+            "error[T0001]: function `output` received tainted input",
+            "error[T0001]: function `Logger::log` received tainted input",
+        ],
+        "{}",
+        stderr
+    );
+    assert!(stderr.contains("--> main.rs:4:5"), "{}", stderr);
+    assert!(stderr.contains("--> main.rs:11:12"), "{}", stderr);
+
+    let _ = fs::remove_dir_all(&dir);
+}
+
+/// Compile a crate of `taint-annotations` into `dir`.
+fn compile(dir: &Path, file: &str, args: &[&str]) {
+    let output = Command::new(taint_path())
+        .current_dir(dir)
+        .arg(Path::new(env!("CARGO_MANIFEST_DIR")).join(file))
+        .args(["--edition", "2018", "-L", "."])
+        .args(["--cfg", "feature=\"attributes\""])
+        .args(args)
+        .output()
+        .expect("failed to run taint");
+    assert!(
+        output.status.success(),
+        "{}",
+        String::from_utf8_lossy(&output.stderr)
+    );
+}
+
+/// Get the path of the taint executable, as in `compiletest.rs`.
+fn taint_path() -> PathBuf {
+    PathBuf::from(option_env!("TAINT").unwrap_or(env!("CARGO_BIN_EXE_taint")))
+}
@@ -0,0 +1,114 @@
+//! Test that findings in the baseline are not reported, even once they move to other lines,
+//! unless they come from other sources.
+
+use std::{
+    env, fs,
+    path::{Path, PathBuf},
+    process::Command,
+};
+
+const PROGRAM: &str = r#"
+#![feature(register_tool)]
+#![register_tool(taint)]
+
+fn main() {
+    let val = input();
+    log(val);
+    output(val);
+}
+
+fn log(val: i32) {
+    output(val);
+}
+
+#[taint::source]
+fn input() -> i32 {
+    15
+}
+
+#[taint::source]
+#[allow(dead_code)]
+fn secret() -> i32 {
+    16
+}
+
+#[taint::sink]
+fn output(_: i32) {}
+"#;
+
+const TAINTED: &str = "function `output` received tainted input";
+
+#[test]
+fn only_new_findings_are_reported() {
+    let dir = env::temp_dir().join(format!("taint-baseline-{}", std::process::id()));
+    let _ = fs::remove_dir_all(&dir);
+    fs::create_dir_all(&dir).unwrap();
+    fs::write(dir.join("main.rs"), PROGRAM).unwrap();
+
+    // The first run writes the baseline, and so reports nothing.
+    let (success, first) = run(&dir);
+    assert!(success, "{}", first);
+    assert!(
+        first.contains("wrote 2 findings to the taint baseline"),
+        "{}",
+        first
+    );
+    assert_eq!(
+        fs::read_to_string(dir.join("baseline")).unwrap(),
+        "finding\tlog\toutput\tinput\nfinding\tmain\toutput\tinput\n"
+    );
+
+    // Moving the findings to other lines does not make them new.
+    fs::write(
+        dir.join("main.rs"),
+        PROGRAM.replace("fn main() {\n", "fn main() {\n\n    // A comment.\n"),
+    )
+    .unwrap();
+    let (success, moved) = run(&dir);
+    assert!(success, "{}", moved);
+    assert_eq!(moved.matches(TAINTED).count(), 0, "{}", moved);
+
+    // A second call to the same sink in the same function is new.
+    fs::write(
+        dir.join("main.rs"),
+        PROGRAM.replace(
+            "fn log(val: i32) {\n",
+            "fn log(val: i32) {\n    output(val);\n",
+        ),
+    )
+    .unwrap();
+    let (success, added) = run(&dir);
+    assert!(!success, "{}", added);
+    assert_eq!(added.matches(TAINTED).count(), 1, "{}", added);
+
+    // The same calls with taint from another source are new as well.
+    fs::write(
+        dir.join("main.rs"),
+        PROGRAM.replace("let val = input();", "let val = secret();"),
+    )
+    .unwrap();
+    let (success, other) = run(&dir);
+    assert!(!success, "{}", other);
+    assert_eq!(other.matches(TAINTED).count(), 2, "{}", other);
+
+    let _ = fs::remove_dir_all(&dir);
+}
+
+fn run(dir: &Path) -> (bool, String) {
+    let output = Command::new(taint_path())
+        .arg(dir.join("main.rs"))
+        .args(["--edition", "2018", "--out-dir"])
+        .arg(dir)
+        .arg(format!("--taint-baseline={}", dir.join("baseline").display()))
+        .output()
+        .expect("failed to run taint");
+    (
+        output.status.success(),
+        String::from_utf8(output.stderr).unwrap(),
+    )
+}
+
+/// Get the path of the taint executable, as in `compiletest.rs`.
+fn taint_path() -> PathBuf {
+    PathBuf::from(option_env!("TAINT").unwrap_or(env!("CARGO_BIN_EXE_taint")))
+}
@@ -0,0 +1,99 @@
+//! Test that the call graph discovered by the analysis is written out, with the contexts of each function and the calls which carried taint.
+
+use std::{
+    env, fs,
+    path::{Path, PathBuf},
+    process::Command,
+};
+
+const PROGRAM: &str = r#"
+#![feature(register_tool)]
+#![register_tool(taint)]
+
+fn main() {
+    let val = input();
+    log(val);
+    log(0);
+}
+
+fn log(val: i32) {
+    output(val);
+}
+
+#[taint::source]
+fn input() -> i32 {
+    15
+}
+
+#[taint::sink]
+fn output(_: i32) {}
+"#;
+
+#[test]
+fn call_graph_is_exported() {
+    let dir = env::temp_dir().join(format!("taint-call-graph-{}", std::process::id()));
+    let _ = fs::remove_dir_all(&dir);
+    fs::create_dir_all(&dir).unwrap();
+    fs::write(dir.join("main.rs"), PROGRAM).unwrap();
+
+    let json = run(&dir, "graph.json");
+    assert!(
+        json.contains(r#"{"name": "log", "sink": false, "contexts": ["0", "1"]}"#),
+        "{}",
+        json
+    );
+    assert!(
+        json.contains(r#"{"name": "output", "sink": true, "contexts": ["0", "1"]}"#),
+        "{}",
+        json
+    );
+    // The tainted call and the clean call to `log` are made from different places.
+    assert!(
+        json.contains(r#""caller": "main", "callee": "log", "span": "main.rs:7:5", "tainted": true, "returns_taint": false, "contexts": [["", "1"]]"#),
+        "{}",
+        json
+    );
+    assert!(
+        json.contains(r#""caller": "main", "callee": "log", "span": "main.rs:8:5", "tainted": false, "returns_taint": false, "contexts": [["", "0"]]"#),
+        "{}",
+        json
+    );
+    assert!(
+        json.contains(r#""caller": "log", "callee": "output", "span": "main.rs:12:5", "tainted": true, "returns_taint": false, "contexts": [["0", "0"], ["1", "1"]]"#),
+        "{}",
+        json
+    );
+
+    let dot = run(&dir, "graph.dot");
+    assert!(dot.starts_with("digraph taint {\n"), "{}", dot);
+    assert!(
+        dot.contains(r#""output" [label="output\n(0)\n(1)", shape=box];"#),
+        "{}",
+        dot
+    );
+    assert!(
+        dot.contains(r#""main" -> "log" [label="main.rs:7:5\n() -> (1)", color=red];"#),
+        "{}",
+        dot
+    );
+
+    let _ = fs::remove_dir_all(&dir);
+}
+
+/// Analyze the program and return the call graph written to `file`.
+fn run(dir: &Path, file: &str) -> String {
+    let output = Command::new(taint_path())
+        .current_dir(dir)
+        .arg("main.rs")
+        .args(["--edition", "2018"])
+        .arg(format!("--taint-call-graph={}", file))
+        .output()
+        .expect("failed to run taint");
+    assert!(dir.join(file).exists(), "{}", String::from_utf8_lossy(&output.stderr));
+    fs::read_to_string(dir.join(file)).unwrap()
+}
+
+/// Get the path of the taint executable, as in `compiletest.rs`.
+fn taint_path() -> PathBuf {
+    PathBuf::from(option_env!("TAINT").unwrap_or(env!("CARGO_BIN_EXE_taint")))
+}
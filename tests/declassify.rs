@@ -0,0 +1,80 @@
+//! Test that a value passed through `declassify!` is no longer tainted,
+//! and that every declassification is written as JSON, whether or not the analysis reached it.
+
+use std::{env, fs, path::PathBuf, process::Command};
+
+const PROGRAM: &str = r#"
+#![feature(register_tool)]
+#![register_tool(taint)]
+
+extern crate taint_annotations;
+
+fn main() {
+    let val = input();
+    output(taint_annotations::declassify!(val, "the \"value\" is public"));
+    output(taint_annotations::declassify!(1, "constant"));
+}
+
+#[allow(dead_code)]
+fn unused() -> i32 {
+    taint_annotations::declassify!(input(), "never called")
+}
+
+#[taint::source]
+fn input() -> i32 {
+    15
+}
+
+#[taint::sink]
+fn output(_: i32) {}
+"#;
+
+const DECLASSIFICATIONS: &str = r#"{
+  "declassifications": [
+    {"function": "main", "span": "main.rs:9:12", "reason": "the \"value\" is public", "tainted": true},
+    {"function": "main", "span": "main.rs:10:12", "reason": "constant", "tainted": false},
+    {"function": "unused", "span": "main.rs:15:5", "reason": "never called", "tainted": false}
+  ]
+}
+"#;
+
+#[test]
+fn declassified_values_are_not_tainted() {
+    let dir = env::temp_dir().join(format!("taint-declassify-{}", std::process::id()));
+    let _ = fs::remove_dir_all(&dir);
+    fs::create_dir_all(&dir).unwrap();
+    fs::write(dir.join("main.rs"), PROGRAM).unwrap();
+
+    let annotations = PathBuf::from(env!("CARGO_MANIFEST_DIR")).join("annotations/src/lib.rs");
+    let output = Command::new(taint_path())
+        .current_dir(&dir)
+        .arg(annotations)
+        .args(["--edition", "2018", "--crate-type", "lib"])
+        .args(["--crate-name", "taint_annotations", "--out-dir", "."])
+        .output()
+        .expect("failed to run taint");
+    assert!(
+        output.status.success(),
+        "{}",
+        String::from_utf8_lossy(&output.stderr)
+    );
+
+    let output = Command::new(taint_path())
+        .current_dir(&dir)
+        .arg("main.rs")
+        .args(["--edition", "2018", "-L", "."])
+        .arg("--taint-report-declassifications=declassifications.json")
+        .output()
+        .expect("failed to run taint");
+    let stderr = String::from_utf8(output.stderr).unwrap();
+    assert!(output.status.success(), "{}", stderr);
+    let declassifications = fs::read_to_string(dir.join("declassifications.json")).expect(&stderr);
+    assert_eq!(declassifications, DECLASSIFICATIONS);
+
+    let _ = fs::remove_dir_all(&dir);
+}
+
+/// Get the path of the taint executable, as in `compiletest.rs`.
+fn taint_path() -> PathBuf {
+    PathBuf::from(option_env!("TAINT").unwrap_or(env!("CARGO_BIN_EXE_taint")))
+}
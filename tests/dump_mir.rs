@@ -0,0 +1,97 @@
+//! Test that the MIR of a function is written with its taint, once for each context it is analyzed in.
+
+use std::{
+    env, fs,
+    path::{Path, PathBuf},
+    process::Command,
+};
+
+const PROGRAM: &str = r#"
+#![feature(register_tool)]
+#![register_tool(taint)]
+
+fn main() {
+    let val = input();
+    log(val);
+    log(0);
+}
+
+fn log(val: i32) -> i32 {
+    let r = &val;
+    output(*r);
+    input()
+}
+
+#[taint::source]
+fn input() -> i32 {
+    15
+}
+
+#[taint::sink]
+fn output(_: i32) {}
+"#;
+
+#[test]
+fn mir_is_dumped_with_taint() {
+    let dir = env::temp_dir().join(format!("taint-dump-mir-{}", std::process::id()));
+    let _ = fs::remove_dir_all(&dir);
+    fs::create_dir_all(&dir).unwrap();
+    fs::write(dir.join("main.rs"), PROGRAM).unwrap();
+
+    let stderr = run(&dir, "log");
+    let tainted = fs::read_to_string(dir.join("log.1.taint.mir")).expect(&stderr);
+    assert!(
+        tainted.starts_with("// The taint analysis of `log` with arguments (1).\n"),
+        "{}",
+        tainted
+    );
+    assert!(
+        tainted.contains("// tainted: {_0, _1, _2, _4}\n"),
+        "{}",
+        tainted
+    );
+
+    // The alias shows from the statement which takes the reference on.
+    let reference = tainted.find("_2 = &_1;").expect(&tainted);
+    assert!(
+        !tainted[..reference].contains("// aliases: {_2"),
+        "{}",
+        tainted
+    );
+    assert!(
+        tainted[reference..].contains("// aliases: {_2 -> {_1}}\n"),
+        "{}",
+        tainted
+    );
+
+    // The return place is only tainted once the source returns.
+    let clean = fs::read_to_string(dir.join("log.0.taint.mir")).expect(&stderr);
+    let source = clean.find("input()").expect(&clean);
+    assert!(!clean[..source].contains("// tainted: {_"), "{}", clean);
+    assert!(clean[source..].contains("// tainted: {_0}\n"), "{}", clean);
+
+    let stderr = run(&dir, "missing");
+    assert!(
+        stderr.contains("warning: `missing` was not analyzed, so its MIR was not written"),
+        "{}",
+        stderr
+    );
+
+    let _ = fs::remove_dir_all(&dir);
+}
+
+fn run(dir: &Path, function: &str) -> String {
+    let output = Command::new(taint_path())
+        .arg(dir.join("main.rs"))
+        .args(["--edition", "2018", "--out-dir"])
+        .arg(dir)
+        .arg(format!("--taint-dump-mir={}", function))
+        .output()
+        .expect("failed to run taint");
+    String::from_utf8(output.stderr).unwrap()
+}
+
+/// Get the path of the taint executable, as in `compiletest.rs`.
+fn taint_path() -> PathBuf {
+    PathBuf::from(option_env!("TAINT").unwrap_or(env!("CARGO_BIN_EXE_taint")))
+}
@@ -0,0 +1,129 @@
+//! Test that every error code the analysis emits can be explained with `taint --explain`,
+//! and that the examples in the explanations do what they say.
+//! The examples may use the `taint-annotations` crate, as `extern crate taint_annotations`.
+
+use std::{
+    env, fs,
+    path::{Path, PathBuf},
+    process::Command,
+};
+
+#[test]
+fn every_emitted_code_is_explained() {
+    let mut codes = Vec::new();
+    emitted_codes(Path::new("src"), &mut codes);
+    codes.sort();
+    codes.dedup();
+    assert!(!codes.is_empty(), "no error codes were found in `src`");
+
+    let dir = env::temp_dir().join(format!("taint-explain-{}", std::process::id()));
+    let _ = fs::remove_dir_all(&dir);
+    fs::create_dir_all(&dir).unwrap();
+    build_annotations(&dir);
+
+    for code in &codes {
+        let output = Command::new(taint_path())
+            .args(["--explain", code])
+            .output()
+            .expect("failed to run taint");
+        let explanation = String::from_utf8(output.stdout).unwrap();
+        assert!(
+            output.status.success() && !explanation.is_empty(),
+            "`{}` has no explanation: {}",
+            code,
+            String::from_utf8_lossy(&output.stderr)
+        );
+
+        // The first example has the error, and the second fixes it.
+        let examples = examples(&explanation);
+        assert!(examples.len() >= 2, "`{}` needs an example and a fix", code);
+        let (success, stderr) = compile(&dir, &examples[0]);
+        assert!(
+            !success && stderr.contains(&format!("[{}]", code)),
+            "the example of `{}` does not emit it: {}",
+            code,
+            stderr
+        );
+        let (success, stderr) = compile(&dir, &examples[1]);
+        assert!(
+            success,
+            "the fixed example of `{}` does not compile: {}",
+            code, stderr
+        );
+    }
+
+    let _ = fs::remove_dir_all(&dir);
+}
+
+#[test]
+fn unknown_codes_are_rejected() {
+    let output = Command::new(taint_path())
+        .args(["--explain", "T9999"])
+        .output()
+        .expect("failed to run taint");
+    assert!(!output.status.success());
+    assert!(String::from_utf8_lossy(&output.stderr).contains("not a valid taint error code"));
+}
+
+/// The codes given to `error_code!` in the sources, e.g. `error_code!(T0001)`.
+fn emitted_codes(dir: &Path, codes: &mut Vec<String>) {
+    for entry in fs::read_dir(dir).unwrap() {
+        let path = entry.unwrap().path();
+        if path.is_dir() {
+            emitted_codes(&path, codes);
+        } else if path.extension().map_or(false, |ext| ext == "rs") {
+            let source = fs::read_to_string(&path).unwrap();
+            for (i, _) in source.match_indices("error_code!(") {
+                let code = &source[i + "error_code!(".len()..];
+                codes.push(code[..code.find(')').unwrap()].to_owned());
+            }
+        }
+    }
+}
+
+/// The code blocks of an explanation.
+fn examples(explanation: &str) -> Vec<String> {
+    explanation
+        .split("```")
+        .skip(1)
+        .step_by(2)
+        .map(|block| block.trim_start_matches("rust").to_owned())
+        .collect()
+}
+
+/// Build the `taint-annotations` crate into `dir`, where the examples find it.
+fn build_annotations(dir: &Path) {
+    let output = Command::new(taint_path())
+        .arg(Path::new(env!("CARGO_MANIFEST_DIR")).join("annotations/src/lib.rs"))
+        .args(["--edition", "2018", "--crate-type", "lib"])
+        .args(["--crate-name", "taint_annotations", "--out-dir"])
+        .arg(dir)
+        .output()
+        .expect("failed to run taint");
+    assert!(
+        output.status.success(),
+        "{}",
+        String::from_utf8_lossy(&output.stderr)
+    );
+}
+
+fn compile(dir: &Path, example: &str) -> (bool, String) {
+    fs::write(dir.join("main.rs"), example).unwrap();
+    let output = Command::new(taint_path())
+        .arg(dir.join("main.rs"))
+        .args(["--edition", "2018", "--crate-type", "lib", "-L"])
+        .arg(dir)
+        .arg("--out-dir")
+        .arg(dir)
+        .output()
+        .expect("failed to run taint");
+    (
+        output.status.success(),
+        String::from_utf8(output.stderr).unwrap(),
+    )
+}
+
+/// Get the path of the taint executable, as in `compiletest.rs`.
+fn taint_path() -> PathBuf {
+    PathBuf::from(option_env!("TAINT").unwrap_or(env!("CARGO_BIN_EXE_taint")))
+}
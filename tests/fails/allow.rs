@@ -0,0 +1,38 @@
+// Test that findings in code marked with `taint::allow` are not reported,
+// and that a `taint::allow` which suppresses nothing gets a warning.
+
+#![feature(register_tool)]
+#![feature(stmt_expr_attributes)]
+#![register_tool(taint)]
+
+fn main() {
+    let val = input();
+
+    #[taint::allow(reason = "the value is only logged")]
+    output(val);
+
+    let _ = #[taint::allow(reason = "the value is only logged")]
+    output(val);
+
+    log(val);
+    output(val); //~ ERROR function `output` received tainted input
+    clean();
+}
+
+#[taint::allow(reason = "the value is only logged")]
+fn log(val: i32) {
+    output(val);
+}
+
+#[taint::allow(reason = "nothing tainted is written here")] //~ WARN unused `taint::allow`: no findings were suppressed
+fn clean() {
+    output(0);
+}
+
+#[taint::source]
+fn input() -> i32 {
+    15
+}
+
+#[taint::sink]
+fn output(_: i32) {}
@@ -0,0 +1,22 @@
+// Test that `taint::allow` without a reason is an error, and does not suppress anything.
+
+#![feature(register_tool)]
+#![register_tool(taint)]
+
+fn main() {
+    let val = input();
+    log(val);
+}
+
+#[taint::allow] //~ ERROR `taint::allow` requires a reason
+fn log(val: i32) {
+    output(val); //~ ERROR function `output` received tainted input
+}
+
+#[taint::source]
+fn input() -> i32 {
+    15
+}
+
+#[taint::sink]
+fn output(_: i32) {}
@@ -0,0 +1,28 @@
+// Test that a function is given at most one role, and no attribute is given twice,
+// while a type may be both tainted and a sink type.
+
+#![feature(register_tool)]
+#![register_tool(taint)]
+#![allow(dead_code)]
+
+#[taint::source]
+#[taint::sink] //~ ERROR `taint::sink` conflicts with `taint::source` [T0006]
+fn both() {}
+
+#[taint::sanitizer]
+#[taint::sanitizer] //~ ERROR `taint::sanitizer` is given more than once [T0006]
+fn twice() {}
+
+#[taint::sink(label = "sql")]
+#[taint::sink(label = "log")] //~ ERROR `taint::sink` is given more than once [T0006]
+fn labels() {}
+
+#[taint::tainted_type]
+#[taint::sink_type]
+struct Token(String);
+
+#[taint::tainted_type(kind = "secret")]
+#[taint::tainted_type(kind = "pii")] //~ ERROR `taint::tainted_type` is given more than once [T0006]
+struct Password(String);
+
+fn main() {}
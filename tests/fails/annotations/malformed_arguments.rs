@@ -0,0 +1,33 @@
+// Test that we reject arguments which our annotations do not take.
+
+#![feature(register_tool)]
+#![feature(stmt_expr_attributes)]
+#![register_tool(taint)]
+#![allow(dead_code)]
+
+#[taint::source(label = "input")] //~ ERROR `taint::source` does not take this argument [T0004]
+fn input() -> i32 {
+    15
+}
+
+#[taint::sanitizer = "escape"] //~ ERROR malformed `taint::sanitizer` attribute [T0004]
+fn escape(val: i32) -> i32 {
+    val
+}
+
+#[taint::sink(label = 1)] //~ ERROR the `label` of `taint::sink` must be a string [T0004]
+fn number(_: i32) {}
+
+#[taint::sink(label = "sql", label = "log")] //~ ERROR the `label` of `taint::sink` is given more than once [T0004]
+fn twice(_: i32) {}
+
+#[taint::sink(kind = "sql")] //~ ERROR `taint::sink` does not take this argument [T0004]
+fn kind(_: i32) {}
+
+#[taint::sink("sql")] //~ ERROR `taint::sink` does not take this argument [T0004]
+fn literal(_: i32) {}
+
+fn main() {
+    #[taint::allow(reason = "checked", by = "me")] //~ ERROR `taint::allow` does not take this argument [T0004]
+    number(input());
+}
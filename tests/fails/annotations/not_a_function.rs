@@ -0,0 +1,52 @@
+// Test that we reject roles given to anything but a function, impl block or module,
+// and types given to anything but a struct or enum.
+
+#![feature(register_tool)]
+#![feature(stmt_expr_attributes)]
+#![feature(custom_inner_attributes)]
+#![register_tool(taint)]
+#![taint::sink_type] //~ ERROR `taint::sink_type` can only be applied to structs and enums [T0005]
+#![allow(dead_code)]
+
+#[taint::source] //~ ERROR `taint::source` can only be applied to functions, impl blocks and modules [T0005]
+struct Input {
+    #[taint::sink] //~ ERROR `taint::sink` can only be applied to functions, impl blocks and modules [T0005]
+    value: i32,
+}
+
+#[taint::sanitizer] //~ ERROR `taint::sanitizer` can only be applied to functions, impl blocks and modules [T0005]
+const CLEAN: i32 = 0;
+
+#[taint::none] //~ ERROR `taint::none` can only be applied to functions, impl blocks and modules [T0005]
+static NONE: i32 = 0;
+
+#[taint::source] //~ ERROR `taint::source` can only be applied to functions, impl blocks and modules [T0005]
+trait Source {
+    #[taint::source]
+    fn input() -> i32;
+}
+
+#[taint::tainted_type] //~ ERROR `taint::tainted_type` can only be applied to structs and enums [T0005]
+fn secret() {}
+
+#[taint::sink_type] //~ ERROR `taint::sink_type` can only be applied to structs and enums [T0005]
+union Bits {
+    int: u32,
+    float: f32,
+}
+
+#[taint::tainted_type] //~ ERROR `taint::tainted_type` can only be applied to structs and enums [T0005]
+union Key {
+    bytes: [u8; 4],
+    int: u32,
+}
+
+fn main() {
+    secret();
+
+    #[taint::source] //~ ERROR `taint::source` can only be applied to functions, impl blocks and modules [T0005]
+    let _val = 15;
+
+    let _ = #[taint::sanitizer] //~ ERROR `taint::sanitizer` can only be applied to functions, impl blocks and modules [T0005]
+    CLEAN;
+}
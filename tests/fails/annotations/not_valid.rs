@@ -3,5 +3,10 @@
 #![feature(register_tool)]
 #![register_tool(taint)]
 
-#[taint::not_valid] //~ ERROR Taint attribute `not_valid` is invalid. We currently only support `source`, `sink`, and `sanitizer` [T0002]
-fn main() {}
+#[taint::not_valid] //~ ERROR Taint attribute `not_valid` is invalid. We currently only support `source`, `sink`, `sanitizer`, `none`, `tainted_type`, `sink_type`, and `allow` [T0002]
+fn main() {
+    nested();
+}
+
+#[taint::sink::label] //~ ERROR Taint attribute `sink::label` is invalid. We currently only support `source`, `sink`, `sanitizer`, `none`, `tainted_type`, `sink_type`, and `allow` [T0002]
+fn nested() {}
@@ -0,0 +1,49 @@
+// Test that taint assertions are checked where they are made, in every context their function is analyzed in.
+
+// aux-build:taint_annotations.rs
+
+#![feature(register_tool)]
+#![register_tool(taint)]
+
+extern crate taint_annotations;
+
+use taint_annotations::{assert_clean, assert_tainted, declassify};
+
+fn main() {
+    let val = input();
+    assert_tainted!(val);
+    assert_clean!(val); //~ ERROR taint assertion failed: `val` may be tainted here [T0007]
+
+    let clean = 1;
+    assert_clean!(clean);
+    assert_tainted!(clean); //~ ERROR taint assertion failed: `clean` is never tainted here [T0007]
+
+    let sum = val + clean;
+    assert_tainted!(sum);
+    assert_clean!(declassify!(sum, "for the test"));
+
+    let mut copy = clean;
+    assert_clean!(copy);
+    copy = val;
+    assert_tainted!(copy);
+
+    // The argument is tainted in one of the contexts `check` is analyzed in.
+    check(val);
+    check(clean);
+}
+
+fn check(arg: i32) {
+    assert_tainted!(arg);
+    assert_clean!(arg); //~ ERROR taint assertion failed: `arg` may be tainted here [T0007]
+}
+
+// An assertion the analysis never reaches does not hold.
+#[allow(dead_code)]
+fn unused() {
+    assert_tainted!(input()); //~ ERROR taint assertion failed: `input()` is never tainted here [T0007]
+}
+
+#[taint::source]
+fn input() -> i32 {
+    15
+}
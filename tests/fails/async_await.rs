@@ -0,0 +1,68 @@
+// Test whether taint is tracked into `async fn` bodies and across `.await` points.
+
+#![feature(register_tool)]
+#![register_tool(taint)]
+
+use std::future::Future;
+use std::pin::{pin, Pin};
+use std::task::{Context, Poll, RawWaker, RawWakerVTable, Waker};
+
+fn main() {
+    block_on(handle(input()));
+    let val = block_on(forward(input()));
+    output(val); //~ ERROR function `output` received tainted input [T0001]
+}
+
+async fn handle(val: i32) {
+    let val = forward(val).await;
+    output(val); //~ ERROR function `output` received tainted input [T0001]
+}
+
+async fn forward(val: i32) -> i32 {
+    // The taint must survive being saved in the generator while it is suspended.
+    YieldNow(false).await;
+    val
+}
+
+struct YieldNow(bool);
+
+impl Future for YieldNow {
+    type Output = ();
+
+    fn poll(mut self: Pin<&mut Self>, _cx: &mut Context<'_>) -> Poll<()> {
+        if self.0 {
+            Poll::Ready(())
+        } else {
+            self.0 = true;
+            Poll::Pending
+        }
+    }
+}
+
+fn block_on<F: Future>(future: F) -> F::Output {
+    let mut future = pin!(future);
+    let waker = unsafe { Waker::from_raw(noop_raw_waker()) };
+    let mut cx = Context::from_waker(&waker);
+    loop {
+        if let Poll::Ready(output) = future.as_mut().poll(&mut cx) {
+            return output;
+        }
+    }
+}
+
+fn noop_raw_waker() -> RawWaker {
+    fn clone(_: *const ()) -> RawWaker {
+        noop_raw_waker()
+    }
+    fn noop(_: *const ()) {}
+    static VTABLE: RawWakerVTable = RawWakerVTable::new(clone, noop, noop, noop);
+    RawWaker::new(std::ptr::null(), &VTABLE)
+}
+
+#[taint::source]
+fn input() -> i32 {
+    15
+}
+
+#[taint::sink]
+fn output(_: i32) {}
@@ -0,0 +1,30 @@
+// no-prefer-dynamic
+
+#![crate_type = "lib"]
+#![feature(register_tool)]
+#![register_tool(taint)]
+
+use std::sync::Mutex;
+
+pub static mut LATEST: i32 = 0;
+static SECRET: Mutex<i32> = Mutex::new(0);
+
+#[taint::source]
+fn read_secret() -> i32 {
+    42
+}
+
+pub fn load() {
+    unsafe {
+        LATEST = read_secret();
+    }
+}
+
+pub fn fill_secret() {
+    *SECRET.lock().unwrap() = read_secret();
+}
+
+/// The static is private, so callers only reach it through the returned reference.
+pub fn secret() -> &'static Mutex<i32> {
+    &SECRET
+}
@@ -0,0 +1,37 @@
+// no-prefer-dynamic
+
+#![crate_type = "lib"]
+#![feature(register_tool)]
+#![register_tool(taint)]
+
+#[taint::source]
+pub fn read_secret() -> i32 {
+    42
+}
+
+#[taint::sink]
+pub fn publish(_: i32) {}
+
+pub fn log(val: i32) {
+    publish(val);
+}
+
+pub fn log_sum(a: i32, b: i32) {
+    publish(a + b);
+}
+
+pub fn store(val: i32, out: &mut i32) {
+    *out = val;
+}
+
+pub fn scrub(_val: i32) -> i32 {
+    0
+}
+
+#[taint::tainted_type(kind = "secret")]
+pub struct Token(pub i32);
+
+pub trait Publisher {
+    #[taint::sink]
+    fn publish_to(&self, val: i32);
+}
@@ -0,0 +1,10 @@
+// The `taint-annotations` crate, built under its own name for the tests which use its macros.
+
+// no-prefer-dynamic
+
+#![crate_type = "lib"]
+
+#[path = "../../../annotations/src/lib.rs"]
+mod annotations;
+
+pub use annotations::*;
@@ -0,0 +1,39 @@
+// Test that a function which visits too many basic blocks gets a conservative summary, with a warning naming it.
+
+// compile-flags: --taint-max-blocks=12
+
+#![feature(register_tool)]
+#![register_tool(taint)]
+
+fn main() {
+    let val = input();
+    output(small(val));
+    output(large(val)); //~ ERROR function `output` received tainted input
+}
+
+fn small(_: i32) -> i32 {
+    0
+}
+
+fn large(val: i32) -> i32 { //~ WARN the taint analysis of `large` visited more than 12 basic blocks
+    let mut sum = 0;
+    for i in 0..val {
+        if i % 2 == 0 {
+            sum += i;
+        } else {
+            sum -= 1;
+        }
+    }
+    log(sum);
+    0
+}
+
+fn log(_: i32) {}
+
+#[taint::source]
+fn input() -> i32 {
+    15
+}
+
+#[taint::sink]
+fn output(_: i32) {}
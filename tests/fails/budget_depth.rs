@@ -0,0 +1,29 @@
+// Test that a function nested in too many analyses gets a conservative summary, with a warning naming it.
+
+// compile-flags: --taint-max-depth=2
+
+#![feature(register_tool)]
+#![register_tool(taint)]
+
+fn main() {
+    let val = input();
+    first(val);
+}
+
+fn first(val: i32) {
+    // The value returned by `second` is assumed to be tainted, since it was not analyzed.
+    let ret = second(val);
+    output(ret); //~ ERROR function `output` received tainted input
+}
+
+fn second(_: i32) -> i32 { //~ WARN the taint analysis of `second` was nested in 2 other analyses
+    0
+}
+
+#[taint::source]
+fn input() -> i32 {
+    15
+}
+
+#[taint::sink]
+fn output(_: i32) {}
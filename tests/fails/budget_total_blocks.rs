@@ -0,0 +1,51 @@
+// Test that once the whole analysis has visited too many basic blocks, the function being analyzed is named,
+// and every function left gets a conservative summary.
+
+// compile-flags: --taint-max-total-blocks=60
+
+#![feature(register_tool)]
+#![register_tool(taint)]
+
+fn main() {
+    let val = input();
+    output(first(val));
+    output(second(val));
+    output(third(val));
+}
+
+fn first(val: i32) -> i32 {
+    output(val); //~ ERROR function `output` received tainted input
+    let mut sum = 0;
+    for i in 0..val {
+        if i % 2 == 0 {
+            sum += i;
+        }
+    }
+    log(sum);
+    0
+}
+
+fn second(val: i32) -> i32 { //~ WARN the taint analysis visited more than 60 basic blocks in total while analyzing `second`
+    let mut sum = 0;
+    for i in 0..val {
+        if i % 3 == 0 {
+            sum += i;
+        }
+    }
+    log(sum);
+    0
+}
+
+fn third(_: i32) -> i32 {
+    0
+}
+
+fn log(_: i32) {}
+
+#[taint::source]
+fn input() -> i32 {
+    15
+}
+
+#[taint::sink]
+fn output(_: i32) {}
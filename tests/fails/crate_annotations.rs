@@ -0,0 +1,18 @@
+// Test that every function in a crate marked as a sink is one, unless it is marked `taint::none`.
+
+#![feature(register_tool)]
+#![feature(custom_inner_attributes)]
+#![register_tool(taint)]
+#![taint::sink]
+
+fn output(_: i32) {}
+
+#[taint::source]
+fn input() -> i32 {
+    15
+}
+
+#[taint::none]
+fn main() {
+    output(input()); //~ ERROR function `output` received tainted input
+}
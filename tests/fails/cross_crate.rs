@@ -0,0 +1,38 @@
+// Test whether taint flows through functions in another crate, using the summaries written when it was compiled.
+
+// aux-build:summarized.rs
+
+#![feature(register_tool)]
+#![register_tool(taint)]
+
+extern crate summarized;
+
+use summarized::Publisher;
+
+fn main() {
+    let val = summarized::read_secret();
+    summarized::log(val); //~ ERROR function `publish` received tainted input [T0001]
+    // Only one argument is tainted in each of the summaries, which are joined for a call with both.
+    summarized::log_sum(val, val); //~ ERROR function `publish` received tainted input [T0001]
+
+    let mut out = 0;
+    summarized::store(val, &mut out);
+    output(out); //~ ERROR function `output` received tainted input [T0001]
+
+    output(summarized::scrub(val));
+
+    let token = summarized::Token(1);
+    output(token.0); //~ ERROR function `output` received tainted input [T0001]
+
+    // The annotation of a trait method applies to the implementations in this crate as well.
+    Channel.publish_to(val); //~ ERROR function `<Channel as summarized::Publisher>::publish_to` received tainted input [T0001]
+}
+
+struct Channel;
+
+impl summarized::Publisher for Channel {
+    fn publish_to(&self, _: i32) {}
+}
+
+#[taint::sink]
+fn output(_: i32) {}
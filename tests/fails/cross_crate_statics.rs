@@ -0,0 +1,21 @@
+// Test whether taint which a function in another crate stores in a static is seen where this crate reads it.
+
+// aux-build:shared_state.rs
+
+#![feature(register_tool)]
+#![register_tool(taint)]
+
+extern crate shared_state;
+
+fn main() {
+    shared_state::load();
+    let latest = unsafe { shared_state::LATEST };
+    output(latest); //~ ERROR function `output` received tainted input [T0001]
+
+    shared_state::fill_secret();
+    let secret = *shared_state::secret().lock().unwrap();
+    output(secret); //~ ERROR function `output` received tainted input [T0001]
+}
+
+#[taint::sink]
+fn output(_: i32) {}
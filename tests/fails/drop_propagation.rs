@@ -0,0 +1,55 @@
+// Test whether sinks in destructors are reached when a tainted value is dropped.
+
+#![feature(register_tool)]
+#![register_tool(taint)]
+
+struct Logger {
+    pending: i32,
+}
+
+impl Drop for Logger {
+    fn drop(&mut self) {
+        output(self.pending); //~ ERROR function `output` received tainted input [T0001]
+    }
+}
+
+// Dropping the outer value runs the destructors of its fields.
+struct Session {
+    _logger: Logger,
+}
+
+// Only taints its target when dropped.
+struct Flush<'a> {
+    target: &'a mut i32,
+}
+
+impl Drop for Flush<'_> {
+    fn drop(&mut self) {
+        *self.target = input();
+    }
+}
+
+fn main() {
+    let _session = Session {
+        _logger: Logger { pending: input() },
+    };
+
+    let mut target = 0;
+    {
+        let _flush = Flush {
+            target: &mut target,
+        };
+    }
+    sink(target); //~ ERROR function `sink` received tainted input [T0001]
+}
+
+#[taint::source]
+fn input() -> i32 {
+    15
+}
+
+#[taint::sink]
+fn output(_: i32) {}
+
+#[taint::sink]
+fn sink(_: i32) {}
@@ -0,0 +1,39 @@
+// Test that every call shares one summary when the analysis is context-insensitive,
+// so a clean argument is merged with a tainted one, whether it is passed before or after it.
+
+// compile-flags: --taint-context=insensitive
+
+#![feature(register_tool)]
+#![register_tool(taint)]
+
+fn main() {
+    let tainted = identity(input());
+    let clean = identity(1);
+    output(clean); //~ ERROR function `output` received tainted input
+    let _ = tainted;
+
+    reversed();
+}
+
+fn reversed() {
+    let clean = copy(1);
+    output(clean); //~ ERROR function `output` received tainted input
+    let tainted = copy(input());
+    let _ = tainted;
+}
+
+fn identity(val: i32) -> i32 {
+    val
+}
+
+fn copy(val: i32) -> i32 {
+    val
+}
+
+#[taint::source]
+fn input() -> i32 {
+    15
+}
+
+#[taint::sink]
+fn output(_: i32) {}
@@ -0,0 +1,44 @@
+// Test that the groups of functions in a level of the call graph find the same sinks as when they are summarized one by one,
+// including through global memory written by one function and read by another which does not call it.
+// Each group only sees what was found before its level started, as when they are analyzed in parallel,
+// which this compiler cannot do.
+
+#![feature(register_tool)]
+#![register_tool(taint)]
+
+static mut LAST: i32 = 0;
+
+fn main() {
+    store();
+    load();
+    direct();
+    shared(1);
+}
+
+fn store() {
+    unsafe {
+        LAST = input();
+    }
+}
+
+fn load() {
+    let val = unsafe { LAST };
+    output(val); //~ ERROR function `output` received tainted input
+}
+
+fn direct() {
+    let val = input();
+    shared(val);
+}
+
+fn shared(val: i32) {
+    output(val); //~ ERROR function `output` received tainted input
+}
+
+#[taint::source]
+fn input() -> i32 {
+    15
+}
+
+#[taint::sink]
+fn output(_: i32) {}
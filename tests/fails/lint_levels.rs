@@ -0,0 +1,50 @@
+// Test that findings are `taint::tainted_sink` lints, whose level can be set in the code,
+// and that sinks with a label get the severity given to it unless the level was set.
+// compile-flags: --taint-severity=log=warn --taint-severity=debug=allow -W warnings
+
+#![feature(register_tool)]
+#![register_tool(taint)]
+
+fn main() {
+    let val = input();
+    output(val); //~ ERROR function `output` received tainted input
+    log(val); //~ WARN function `log` received tainted input
+    debug(val);
+    allowed(val);
+    warned(val);
+    denied(val);
+    unknown();
+}
+
+#[allow(taint::tainted_sink)]
+fn allowed(val: i32) {
+    output(val);
+}
+
+#[warn(taint::tainted_sink)]
+fn warned(val: i32) {
+    output(val); //~ WARN function `output` received tainted input
+}
+
+#[deny(taint::tainted_sink)]
+fn denied(val: i32) {
+    log(val); //~ ERROR function `log` received tainted input
+}
+
+#[allow(taint::invalid_attribute)]
+#[taint::unknown]
+fn unknown() {}
+
+#[taint::source]
+fn input() -> i32 {
+    15
+}
+
+#[taint::sink]
+fn output(_: i32) {}
+
+#[taint::sink(label = "log")]
+fn log(_: i32) {}
+
+#[taint::sink(label = "debug")]
+fn debug(_: i32) {}
@@ -0,0 +1,32 @@
+// Test whether taint follows unwind edges into `catch_unwind`, along with tainted panic payloads.
+
+#![feature(register_tool)]
+#![register_tool(taint)]
+
+use std::panic::{self, AssertUnwindSafe};
+
+fn main() {
+    let mut buffer = 0;
+    let _ = panic::catch_unwind(AssertUnwindSafe(|| fill(&mut buffer)));
+    output(buffer); //~ ERROR function `output` received tainted input [T0001]
+
+    let payload = panic::catch_unwind(|| panic::panic_any(input())).unwrap_err();
+    output(*payload.downcast::<i32>().unwrap()); //~ ERROR function `output` received tainted input [T0001]
+}
+
+// Only taints `buffer` on the way out of a panic.
+fn fill(buffer: &mut i32) {
+    let val = input();
+    if val > 10 {
+        *buffer = val;
+        panic!("value too large");
+    }
+}
+
+#[taint::source]
+fn input() -> i32 {
+    15
+}
+
+#[taint::sink]
+fn output(_: i32) {}
@@ -0,0 +1,21 @@
+// Test that panics with tainted messages are reported when panics are treated as sinks.
+
+// compile-flags: --taint-panic-sinks
+
+#![feature(register_tool)]
+#![register_tool(taint)]
+
+fn main() {
+    let val = input();
+    if val > 10 {
+        panic!("value too large: {}", val); //~ ERROR function `std::rt::panic_fmt` received tainted input [T0001]
+    }
+
+    let msg = format!("missing value after {}", val);
+    let _ = Some(val).expect(&msg); //~ ERROR function `std::option::Option::<i32>::expect` received tainted input [T0001]
+}
+
+#[taint::source]
+fn input() -> i32 {
+    15
+}
@@ -0,0 +1,50 @@
+// Test that taint which only reaches a sink through a recursive call is found,
+// whichever function of the recursion is analyzed first.
+
+#![feature(register_tool)]
+#![register_tool(taint)]
+
+fn main() {
+    ping(4);
+
+    let mut buf = 0;
+    fill(&mut buf, 3);
+}
+
+// The value returned by `ping` is only tainted in its base case,
+// which `pong` only sees through the recursive call.
+fn ping(n: u32) -> i32 {
+    if n == 0 {
+        input()
+    } else {
+        pong(n - 1)
+    }
+}
+
+fn pong(n: u32) -> i32 {
+    if n == 0 {
+        0
+    } else {
+        let val = ping(n - 1);
+        output(val); //~ ERROR function `output` received tainted input
+        val
+    }
+}
+
+// The argument is only tainted in the base case, so the sink is only reached once the recursive call returns.
+fn fill(buf: &mut i32, n: u32) {
+    if n == 0 {
+        *buf = input();
+    } else {
+        fill(buf, n - 1);
+        output(*buf); //~ ERROR function `output` received tainted input
+    }
+}
+
+#[taint::source]
+fn input() -> i32 {
+    15
+}
+
+#[taint::sink]
+fn output(_: i32) {}
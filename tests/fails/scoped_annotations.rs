@@ -0,0 +1,58 @@
+// Test that the functions in an impl block or module marked as a source, sink or sanitizer get that role,
+// unless they have one of their own or are marked `taint::none`.
+
+#![feature(register_tool)]
+#![register_tool(taint)]
+
+struct Database;
+
+#[taint::sink(label = "sql")]
+impl Database {
+    fn execute(&self, _query: i32) {}
+
+    fn insert(&self, _row: i32) {}
+
+    #[taint::none]
+    fn log(&self, _query: i32) {}
+
+    #[taint::sanitizer]
+    fn escape(&self, query: i32) -> i32 {
+        query
+    }
+}
+
+#[taint::source]
+mod input {
+    pub fn read() -> i32 {
+        15
+    }
+
+    #[taint::none]
+    pub mod constants {
+        pub fn zero() -> i32 {
+            0
+        }
+    }
+}
+
+trait Output {
+    #[taint::sink]
+    fn output(&self, val: i32);
+}
+
+impl Output for Database {
+    // Opts out of the annotation of the trait method as well.
+    #[taint::none]
+    fn output(&self, _: i32) {}
+}
+
+fn main() {
+    let db = Database;
+    let val = input::read();
+    db.execute(val); //~ ERROR function `Database::execute` received tainted input
+    db.insert(val); //~ ERROR function `Database::insert` received tainted input
+    db.log(val);
+    db.execute(db.escape(val));
+    db.execute(input::constants::zero());
+    db.output(val);
+}
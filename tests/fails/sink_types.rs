@@ -0,0 +1,53 @@
+// Test that a function with a parameter of a type marked `taint::sink_type` is a sink for it,
+// without being annotated itself.
+
+#![feature(register_tool)]
+#![register_tool(taint)]
+
+#[taint::sink_type(label = "sql")]
+#[derive(Clone, Debug)]
+struct RawSql(String);
+
+impl RawSql {
+    fn new(query: String) -> Self {
+        RawSql(query)
+    }
+
+    // The methods of the type itself are not sinks.
+    fn len(&self) -> usize {
+        self.0.len()
+    }
+}
+
+struct Database;
+
+impl Database {
+    fn execute(&self, _query: &RawSql) {}
+
+    fn prepare(&self, _query: Option<RawSql>, _count: usize) {}
+}
+
+fn main() {
+    let db = Database;
+    let query = RawSql::new(input());
+    let count = query.len();
+    db.execute(&query); //~ ERROR function `Database::execute` received tainted input
+
+    db.prepare(None, count);
+    db.prepare(Some(query), 0); //~ ERROR function `Database::prepare` received tainted input
+
+    db.execute(&RawSql::new(String::from("SELECT 1")));
+
+    // Neither the trait implementations of the type nor the generic functions of the standard library are sinks.
+    let query = RawSql::new(input());
+    let copy = query.clone();
+    println!("{:?}", copy);
+    let mut queries = Vec::new();
+    queries.push(copy);
+    drop(query);
+}
+
+#[taint::source]
+fn input() -> String {
+    String::from("x' OR 1=1")
+}
@@ -0,0 +1,60 @@
+// Test whether taint stored in a static by one function is visible when another function reads it.
+
+#![feature(register_tool)]
+#![feature(local_key_cell_methods)]
+#![feature(thread_local)]
+#![register_tool(taint)]
+
+use std::cell::Cell;
+use std::sync::{Mutex, OnceLock};
+
+static mut BUFFER: i32 = 0;
+static SHARED: Mutex<i32> = Mutex::new(0);
+static CELL: OnceLock<i32> = OnceLock::new();
+
+#[thread_local]
+static mut LOCAL: i32 = 0;
+
+thread_local! {
+    static LAST: Cell<i32> = Cell::new(0);
+    static COUNT: Cell<i32> = Cell::new(0);
+    static CLEAN: Cell<i32> = Cell::new(0);
+}
+
+fn main() {
+    // The reads come first, so that the statics are only tainted on a later iteration.
+    let buffer = unsafe { BUFFER };
+    output(buffer); //~ ERROR function `output` received tainted input [T0001]
+    let shared = *SHARED.lock().unwrap();
+    output(shared); //~ ERROR function `output` received tainted input [T0001]
+    let cell = *CELL.get().unwrap();
+    output(cell); //~ ERROR function `output` received tainted input [T0001]
+    let local = unsafe { LOCAL };
+    output(local); //~ ERROR function `output` received tainted input [T0001]
+    let last = LAST.with(|c| c.get());
+    output(last); //~ ERROR function `output` received tainted input [T0001]
+    output(COUNT.get()); //~ ERROR function `output` received tainted input [T0001]
+    output(CLEAN.with(|c| c.get()));
+
+    store();
+}
+
+fn store() {
+    unsafe {
+        BUFFER = input();
+        LOCAL = input();
+    }
+    *SHARED.lock().unwrap() = input();
+    let _ = CELL.set(input());
+    LAST.with(|c| c.set(input()));
+    COUNT.set(input());
+    CLEAN.with(|c| c.set(1));
+}
+
+#[taint::source]
+fn input() -> i32 {
+    15
+}
+
+#[taint::sink]
+fn output(_: i32) {}
@@ -0,0 +1,52 @@
+// Test that values of a type marked `taint::tainted_type` are tainted, however they are created.
+
+#![feature(register_tool)]
+#![register_tool(taint)]
+
+#[taint::tainted_type(kind = "user-input")]
+struct UserInput(String);
+
+#[taint::tainted_type(kind = "secret")]
+enum SecretKey {
+    Bytes([u8; 4]),
+}
+
+struct Wrapper {
+    input: UserInput,
+}
+
+fn main() {
+    let input = UserInput(String::from("name"));
+    output(&input.0); //~ ERROR function `output` received tainted input
+    log(&input); //~ ERROR function `log::<UserInput>` received tainted input
+
+    let key = SecretKey::Bytes([0; 4]);
+    store(key);
+
+    let wrapper = Wrapper { input: read() };
+    output(&wrapper.input.0); //~ ERROR function `output` received tainted input
+
+    // Only the values of the type are tainted, not those it was created from.
+    let name = String::from("name");
+    let _copy = UserInput(name.clone());
+    output(&name);
+}
+
+fn read() -> UserInput {
+    UserInput(String::new())
+}
+
+fn store(key: SecretKey) {
+    match key {
+        SecretKey::Bytes(bytes) => write(bytes), //~ ERROR function `write` received tainted input
+    }
+}
+
+#[taint::sink]
+fn output(_: &str) {}
+
+#[taint::sink]
+fn log<T>(_: &T) {}
+
+#[taint::sink]
+fn write(_: [u8; 4]) {}
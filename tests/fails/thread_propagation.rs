@@ -0,0 +1,38 @@
+// Test whether taint is shared between threads through channels and shared locks.
+
+#![feature(register_tool)]
+#![register_tool(taint)]
+
+use std::sync::{mpsc, Arc, Mutex};
+use std::thread;
+
+fn main() {
+    let (tx, rx) = mpsc::channel();
+    thread::spawn(move || {
+        tx.send(input()).unwrap();
+    });
+    output(rx.recv().unwrap()); //~ ERROR function `output` received tainted input [T0001]
+
+    let shared = Arc::new(Mutex::new(0));
+    let writer = Arc::clone(&shared);
+    thread::spawn(move || {
+        *writer.lock().unwrap() = input();
+    })
+    .join()
+    .unwrap();
+    output(*shared.lock().unwrap()); //~ ERROR function `output` received tainted input [T0001]
+
+    // Values captured by a scoped thread are tainted in its closure.
+    let val = input();
+    thread::scope(|s| {
+        s.spawn(|| output(val)); //~ ERROR function `output` received tainted input [T0001]
+    });
+}
+
+#[taint::source]
+fn input() -> i32 {
+    15
+}
+
+#[taint::sink]
+fn output(_: i32) {}
@@ -0,0 +1,60 @@
+// Test that the annotations of trait methods apply to every implementation,
+// whether it is called directly, through a generic or through `dyn Trait`,
+// unless the implementation has an annotation of its own.
+// compile-flags: --taint-severity=log=warn -W warnings
+
+#![feature(register_tool)]
+#![register_tool(taint)]
+
+trait Reader {
+    #[taint::source]
+    fn read(&self) -> i32;
+}
+
+trait Writer {
+    #[taint::sink]
+    fn write(&self, val: i32);
+}
+
+struct Stdin;
+struct Trusted;
+struct Stdout;
+struct Log;
+
+impl Reader for Stdin {
+    fn read(&self) -> i32 {
+        15
+    }
+}
+
+impl Reader for Trusted {
+    #[taint::sanitizer]
+    fn read(&self) -> i32 {
+        0
+    }
+}
+
+impl Writer for Stdout {
+    fn write(&self, _: i32) {}
+}
+
+impl Writer for Log {
+    #[taint::sink(label = "log")]
+    fn write(&self, _: i32) {}
+}
+
+fn main() {
+    let val = Stdin.read();
+    Stdout.write(val); //~ ERROR function `<Stdout as Writer>::write` received tainted input
+    Log.write(val); //~ WARN function `<Log as Writer>::write` received tainted input
+    Stdout.write(Trusted.read());
+
+    forward(&Stdin, &Stdout);
+
+    let writer: &dyn Writer = &Log;
+    writer.write(val); //~ ERROR function `<dyn Writer as Writer>::write` received tainted input
+}
+
+fn forward<R: Reader, W: Writer>(reader: &R, writer: &W) {
+    writer.write(reader.read()); //~ ERROR function `<W as Writer>::write` received tainted input
+}
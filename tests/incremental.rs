@@ -0,0 +1,102 @@
+//! Test that summaries are kept between incremental runs, and that functions are analyzed again once they change.
+
+use std::{
+    env, fs,
+    path::{Path, PathBuf},
+    process::Command,
+};
+
+const PROGRAM: &str = r#"
+#![feature(register_tool)]
+#![register_tool(taint)]
+
+fn main() {
+    let val = get_input();
+    log(val);
+    output(val);
+}
+
+fn get_input() -> i32 {
+    input()
+}
+
+fn log(val: i32) {
+    output(val);
+}
+
+#[taint::source]
+fn input() -> i32 {
+    15
+}
+
+#[taint::sink]
+fn output(_: i32) {}
+"#;
+
+const TAINTED: &str = "function `output` received tainted input";
+
+#[test]
+fn summaries_are_reused_until_changed() {
+    let dir = env::temp_dir().join(format!("taint-incremental-{}", std::process::id()));
+    let _ = fs::remove_dir_all(&dir);
+    fs::create_dir_all(&dir).unwrap();
+    fs::write(dir.join("main.rs"), PROGRAM).unwrap();
+
+    // Findings in callees are reported again when their summaries are reused.
+    let first = run(&dir);
+    assert_eq!(first.matches(TAINTED).count(), 2, "{}", first);
+    assert_eq!(run(&dir), first);
+
+    // Pretend that no sinks were reached when the functions were last analyzed,
+    // so there is nothing to report while they are unchanged.
+    let cache = cache_file(&dir);
+    let contents = fs::read_to_string(&cache).unwrap();
+    assert!(contents.contains("finding\t"), "{}", contents);
+    let mut stale = String::new();
+    for line in contents.lines().filter(|l| !l.starts_with("finding\t")) {
+        stale.push_str(line);
+        stale.push('\n');
+    }
+    fs::write(&cache, stale).unwrap();
+    let stale = run(&dir);
+    assert_eq!(stale.matches(TAINTED).count(), 0, "{}", stale);
+
+    // Once `log` changes, it is analyzed again, along with `main` which calls it.
+    fs::write(
+        dir.join("main.rs"),
+        PROGRAM.replace(
+            "fn log(val: i32) {\n    output(val);",
+            "fn log(val: i32) {\n    let copy = val;\n    output(copy);",
+        ),
+    )
+    .unwrap();
+    let changed = run(&dir);
+    assert_eq!(changed.matches(TAINTED).count(), 2, "{}", changed);
+
+    let _ = fs::remove_dir_all(&dir);
+}
+
+fn run(dir: &Path) -> String {
+    let output = Command::new(taint_path())
+        .arg(dir.join("main.rs"))
+        .args(["--edition", "2018", "-C"])
+        .arg(format!("incremental={}", dir.join("incremental").display()))
+        .arg("-o")
+        .arg(dir.join("main"))
+        .output()
+        .expect("failed to run taint");
+    String::from_utf8(output.stderr).unwrap()
+}
+
+fn cache_file(dir: &Path) -> PathBuf {
+    fs::read_dir(dir.join("incremental"))
+        .unwrap()
+        .map(|entry| entry.unwrap().path())
+        .find(|path| path.extension().map_or(false, |ext| ext == "cache"))
+        .expect("no taint cache was written")
+}
+
+/// Get the path of the taint executable, as in `compiletest.rs`.
+fn taint_path() -> PathBuf {
+    PathBuf::from(option_env!("TAINT").unwrap_or(env!("CARGO_BIN_EXE_taint")))
+}
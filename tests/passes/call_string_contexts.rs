@@ -0,0 +1,37 @@
+// Test that calls made from different places do not share a summary
+// when calls are told apart by where they are made from,
+// and that calls made from the same place still do not share one if different arguments are tainted.
+
+// compile-flags: --taint-context=call-string=1
+
+#![feature(register_tool)]
+#![register_tool(taint)]
+
+fn main() {
+    let tainted = identity(input());
+    let clean = identity(1);
+    output(clean);
+    let _ = tainted;
+
+    // `identity` is called from the same place in `wrap` both times.
+    let tainted = wrap(input());
+    let clean = wrap(1);
+    output(clean);
+    let _ = tainted;
+}
+
+fn wrap(val: i32) -> i32 {
+    identity(val)
+}
+
+fn identity(val: i32) -> i32 {
+    val
+}
+
+#[taint::source]
+fn input() -> i32 {
+    15
+}
+
+#[taint::sink]
+fn output(_: i32) {}
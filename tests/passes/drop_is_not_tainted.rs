@@ -0,0 +1,28 @@
+// Test that destructors of clean values do not report their sinks.
+
+#![feature(register_tool)]
+#![register_tool(taint)]
+
+struct Logger {
+    pending: i32,
+}
+
+impl Drop for Logger {
+    fn drop(&mut self) {
+        output(self.pending);
+    }
+}
+
+fn main() {
+    let _logger = Logger { pending: 5 };
+    let val = input();
+    let _sum = val + 1;
+}
+
+#[taint::source]
+fn input() -> i32 {
+    15
+}
+
+#[taint::sink]
+fn output(_: i32) {}
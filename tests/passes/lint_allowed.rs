@@ -0,0 +1,17 @@
+// Test that findings can be allowed on the command line, like any other lint.
+// compile-flags: -A taint::tainted_sink
+
+#![feature(register_tool)]
+#![register_tool(taint)]
+
+fn main() {
+    output(input());
+}
+
+#[taint::source]
+fn input() -> i32 {
+    15
+}
+
+#[taint::sink]
+fn output(_: i32) {}
@@ -0,0 +1,53 @@
+// Test that calls to a method on different objects do not share a summary
+// when calls are told apart by their receivers, even if the receivers have the same taint.
+
+// compile-flags: --taint-context=object
+
+#![feature(register_tool)]
+#![register_tool(taint)]
+
+struct Holder(i32);
+
+impl Holder {
+    fn get(&self, _offset: i32) -> i32 {
+        self.0
+    }
+
+    fn set(&mut self, val: i32) {
+        self.0 = val;
+    }
+
+    fn replace(&mut self, val: i32) {
+        self.set(val);
+    }
+}
+
+fn main() {
+    let tainted = Holder(input());
+    let clean = Holder(1);
+    let _ = tainted.get(0);
+    output(clean.get(0));
+
+    // Both receivers are clean when the methods are called, but they are different objects.
+    let mut first = Holder(1);
+    let mut second = Holder(1);
+    first.set(input());
+    second.set(1);
+    output(second.0);
+
+    // The object is passed along to the methods called on the receiver.
+    let mut third = Holder(1);
+    let mut fourth = Holder(1);
+    third.replace(input());
+    fourth.replace(1);
+    output(fourth.0);
+    let _ = (first.0, third.0);
+}
+
+#[taint::source]
+fn input() -> i32 {
+    15
+}
+
+#[taint::sink]
+fn output(_: i32) {}
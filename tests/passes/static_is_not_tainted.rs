@@ -0,0 +1,27 @@
+// Test that statics which are only ever assigned clean values are not considered tainted.
+
+#![feature(register_tool)]
+#![register_tool(taint)]
+
+static mut BUFFER: i32 = 0;
+
+fn main() {
+    store();
+    let buffer = unsafe { BUFFER };
+    output(buffer);
+}
+
+fn store() {
+    let _ = input();
+    unsafe {
+        BUFFER = 5;
+    }
+}
+
+#[taint::source]
+fn input() -> i32 {
+    15
+}
+
+#[taint::sink]
+fn output(_: i32) {}
@@ -0,0 +1,28 @@
+// Test that taint sent on one channel does not reach the receiver of another.
+
+#![feature(register_tool)]
+#![register_tool(taint)]
+
+use std::sync::mpsc;
+use std::thread;
+
+fn main() {
+    let (tainted_tx, tainted_rx) = mpsc::channel();
+    let (clean_tx, clean_rx) = mpsc::channel();
+    thread::spawn(move || {
+        tainted_tx.send(input()).unwrap();
+    });
+    thread::spawn(move || {
+        clean_tx.send(5).unwrap();
+    });
+    let _ = tainted_rx.recv();
+    output(clean_rx.recv().unwrap());
+}
+
+#[taint::source]
+fn input() -> i32 {
+    15
+}
+
+#[taint::sink]
+fn output(_: i32) {}
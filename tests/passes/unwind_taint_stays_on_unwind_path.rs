@@ -0,0 +1,32 @@
+// Test that taint which only exists while unwinding does not reach the normal return path.
+
+#![feature(register_tool)]
+#![register_tool(taint)]
+
+fn main() {
+    let mut buffer = 0;
+    fill(&mut buffer);
+    output(buffer);
+
+    // Panics are not sinks unless `--taint-panic-sinks` is given.
+    if buffer > 0 {
+        panic!("{}", input());
+    }
+}
+
+// Only taints `buffer` on the way out of a panic.
+fn fill(buffer: &mut i32) {
+    let val = input();
+    if val > 10 {
+        *buffer = val;
+        panic!("value too large");
+    }
+}
+
+#[taint::source]
+fn input() -> i32 {
+    15
+}
+
+#[taint::sink]
+fn output(_: i32) {}
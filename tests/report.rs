@@ -0,0 +1,69 @@
+//! Test that the findings are written as JSON, with the suppressed ones and the reasons they were suppressed for.
+
+use std::{env, fs, path::PathBuf, process::Command};
+
+const PROGRAM: &str = r#"
+#![feature(register_tool)]
+#![register_tool(taint)]
+
+fn main() {
+    let val = input();
+    log(val);
+    output(val);
+}
+
+#[taint::allow(reason = "the \"log\" is private")]
+fn log(val: i32) {
+    output(val);
+}
+
+#[taint::allow(reason = "stale")]
+fn unused() {}
+
+#[taint::source]
+fn input() -> i32 {
+    15
+}
+
+#[taint::sink]
+fn output(_: i32) {}
+"#;
+
+const REPORT: &str = r#"{
+  "findings": [
+    {"sink": "output", "span": "main.rs:8:5"}
+  ],
+  "suppressed": [
+    {"sink": "output", "span": "main.rs:13:5", "allow": "main.rs:11:1", "reason": "the \"log\" is private"}
+  ],
+  "unused_suppressions": [
+    {"allow": "main.rs:16:1", "reason": "stale"}
+  ],
+  "baselined": []
+}
+"#;
+
+#[test]
+fn report_lists_suppressed_findings() {
+    let dir = env::temp_dir().join(format!("taint-report-{}", std::process::id()));
+    let _ = fs::remove_dir_all(&dir);
+    fs::create_dir_all(&dir).unwrap();
+    fs::write(dir.join("main.rs"), PROGRAM).unwrap();
+
+    let output = Command::new(taint_path())
+        .current_dir(&dir)
+        .arg("main.rs")
+        .args(["--edition", "2018", "--taint-report=report.json"])
+        .output()
+        .expect("failed to run taint");
+    let stderr = String::from_utf8(output.stderr).unwrap();
+    let report = fs::read_to_string(dir.join("report.json")).expect(&stderr);
+    assert_eq!(report, REPORT);
+
+    let _ = fs::remove_dir_all(&dir);
+}
+
+/// Get the path of the taint executable, as in `compiletest.rs`.
+fn taint_path() -> PathBuf {
+    PathBuf::from(option_env!("TAINT").unwrap_or(env!("CARGO_BIN_EXE_taint")))
+}
@@ -0,0 +1,80 @@
+//! Test that the summaries a library left behind are ignored once it is rebuilt without them,
+//! rather than looking up functions which no longer exist.
+
+use std::{
+    env, fs,
+    path::{Path, PathBuf},
+    process::Command,
+};
+
+const LIBRARY: &str = r#"
+#![feature(register_tool)]
+#![register_tool(taint)]
+
+#[taint::source]
+pub fn input() -> i32 {
+    15
+}
+
+#[taint::sink]
+pub fn gone(_: i32) {}
+"#;
+
+const PROGRAM: &str = r#"
+extern crate library;
+
+fn main() {
+    library::input();
+}
+"#;
+
+#[test]
+fn stale_summaries_are_ignored() {
+    let dir = env::temp_dir().join(format!("taint-summaries-{}", std::process::id()));
+    let _ = fs::remove_dir_all(&dir);
+    fs::create_dir_all(&dir).unwrap();
+    fs::write(dir.join("library.rs"), LIBRARY).unwrap();
+    fs::write(dir.join("main.rs"), PROGRAM).unwrap();
+
+    let (success, stderr) = run(&dir, &["library.rs", "--crate-type=lib"]);
+    assert!(success, "{}", stderr);
+    let stale = fs::read_to_string(dir.join("liblibrary.taint")).unwrap();
+
+    // Rebuild the library without `gone`, and put back the summaries written before,
+    // as a build which does not run the analysis would leave them.
+    let (kept, _) = LIBRARY.split_once("#[taint::sink]").unwrap();
+    fs::write(dir.join("library.rs"), kept).unwrap();
+    let (success, stderr) = run(&dir, &["library.rs", "--crate-type=lib"]);
+    assert!(success, "{}", stderr);
+    fs::write(dir.join("liblibrary.taint"), stale).unwrap();
+
+    let (success, stderr) = run(&dir, &["main.rs", "-L."]);
+    assert!(success, "{}", stderr);
+    assert!(
+        stderr.contains("warning: ignoring outdated taint summaries in `")
+            && stderr.contains("liblibrary.taint")
+            && stderr.contains("they were written for another build of `library`"),
+        "{}",
+        stderr
+    );
+
+    let _ = fs::remove_dir_all(&dir);
+}
+
+fn run(dir: &Path, args: &[&str]) -> (bool, String) {
+    let output = Command::new(taint_path())
+        .current_dir(dir)
+        .args(args)
+        .args(["--edition", "2018"])
+        .output()
+        .expect("failed to run taint");
+    (
+        output.status.success(),
+        String::from_utf8(output.stderr).unwrap(),
+    )
+}
+
+/// Get the path of the taint executable, as in `compiletest.rs`.
+fn taint_path() -> PathBuf {
+    PathBuf::from(option_env!("TAINT").unwrap_or(env!("CARGO_BIN_EXE_taint")))
+}
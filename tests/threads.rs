@@ -0,0 +1,51 @@
+//! Test that the analysis finds the same sinks with one thread as with several.
+//!
+//! The groups of a level are only analyzed in parallel by a compiler built with the parallel front-end.
+//! Any other compiler rejects more than one thread, which is checked instead.
+
+use std::{
+    env, fs,
+    path::{Path, PathBuf},
+    process::Command,
+};
+
+#[test]
+fn threads_find_the_same_sinks() {
+    let dir = env::temp_dir().join(format!("taint-threads-{}", std::process::id()));
+    let _ = fs::remove_dir_all(&dir);
+    fs::create_dir_all(&dir).unwrap();
+    let program = Path::new(env!("CARGO_MANIFEST_DIR")).join("tests/fails/level_summaries.rs");
+    fs::copy(program, dir.join("main.rs")).unwrap();
+
+    let (success, one) = run(&dir, 1);
+    assert!(!success, "{}", one);
+    assert_eq!(one.matches("error[T0001]").count(), 2, "{}", one);
+
+    let (success, four) = run(&dir, 4);
+    if four.contains("needs a compiler built with the parallel front-end") {
+        assert!(!success, "{}", four);
+    } else {
+        assert_eq!(one, four);
+    }
+
+    let _ = fs::remove_dir_all(&dir);
+}
+
+fn run(dir: &Path, threads: usize) -> (bool, String) {
+    let output = Command::new(taint_path())
+        .current_dir(dir)
+        .arg("main.rs")
+        .args(["--edition", "2018", "--crate-name", "main"])
+        .arg(format!("--taint-threads={}", threads))
+        .output()
+        .expect("failed to run taint");
+    (
+        output.status.success(),
+        String::from_utf8(output.stderr).unwrap(),
+    )
+}
+
+/// Get the path of the taint executable, as in `compiletest.rs`.
+fn taint_path() -> PathBuf {
+    PathBuf::from(option_env!("TAINT").unwrap_or(env!("CARGO_BIN_EXE_taint")))
+}